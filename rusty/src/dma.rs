@@ -0,0 +1,178 @@
+#![allow(unused)]
+
+use crate::{
+  dma_channel_unclaim, dma_claim_unused_channel, irq_set_enabled, irq_set_exclusive_handler,
+};
+
+// Most of the channel functions of the SDK are `static inline`, so the
+// registers are accessed directly, see "2.5.7. List of Registers".
+const DMA_BASE: usize = 0x5000_0000;
+const CHANNEL_STRIDE: usize = 0x40;
+
+const CH_READ_ADDR: usize = 0x00;
+const CH_WRITE_ADDR: usize = 0x04;
+const CH_TRANS_COUNT: usize = 0x08;
+const CH_AL1_CTRL: usize = 0x10;
+
+const INTE0: usize = 0x404;
+const INTS0: usize = 0x40c;
+const MULTI_CHAN_TRIGGER: usize = 0x430;
+const CHAN_ABORT: usize = 0x444;
+
+const CTRL_EN_BITS: u32 = 1 << 0;
+const CTRL_INCR_READ_BITS: u32 = 1 << 4;
+const CTRL_INCR_WRITE_BITS: u32 = 1 << 5;
+const CTRL_CHAIN_TO_LSB: u32 = 11;
+const CTRL_TREQ_SEL_LSB: u32 = 15;
+const CTRL_BUSY_BITS: u32 = 1 << 24;
+
+pub const DMA_IRQ_0: u32 = 11;
+
+static mut IRQ0_CALLBACK: Option<fn()> = None;
+
+fn reg(offset: usize) -> *mut u32 {
+  (DMA_BASE + offset) as _
+}
+
+/// Data request signals pacing a transfer, see "2.5.3.1. System DREQ Table"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dreq {
+  Spi0Tx = 16,
+  Spi0Rx = 17,
+  Spi1Tx = 18,
+  Spi1Rx = 19,
+}
+
+/// A claimed DMA channel, unclaimed when dropped
+#[derive(Debug)]
+pub struct Channel(u32);
+
+impl Channel {
+  /// Claim a free channel, returns `None` if every channel is in use
+  pub fn claim() -> Option<Self> {
+    let channel = unsafe { dma_claim_unused_channel(false) };
+
+    (channel >= 0).then_some(Self(channel as u32))
+  }
+
+  /// Number of the channel
+  pub fn number(&self) -> u32 {
+    self.0
+  }
+
+  fn mask(&self) -> u32 {
+    1 << self.0
+  }
+
+  fn reg(&self, offset: usize) -> *mut u32 {
+    reg(self.0 as usize * CHANNEL_STRIDE + offset)
+  }
+
+  /// Configure a byte-wide transfer of `count` bytes without starting it
+  ///
+  /// # Safety
+  ///
+  /// `read` and `write` must stay valid until the transfer has finished or
+  /// was aborted.
+  pub unsafe fn configure(
+    &self,
+    read: *const u8,
+    incr_read: bool,
+    write: *mut u8,
+    incr_write: bool,
+    count: usize,
+    dreq: Dreq,
+  ) {
+    let mut ctrl = CTRL_EN_BITS
+      // Chaining a channel to itself disables chaining
+      | (self.0 << CTRL_CHAIN_TO_LSB)
+      | ((dreq as u32) << CTRL_TREQ_SEL_LSB);
+
+    if incr_read {
+      ctrl |= CTRL_INCR_READ_BITS;
+    }
+
+    if incr_write {
+      ctrl |= CTRL_INCR_WRITE_BITS;
+    }
+
+    self.reg(CH_READ_ADDR).write_volatile(read as u32);
+    self.reg(CH_WRITE_ADDR).write_volatile(write as u32);
+    self.reg(CH_TRANS_COUNT).write_volatile(count as u32);
+    // Alias without trigger, the channel is started by `start`
+    self.reg(CH_AL1_CTRL).write_volatile(ctrl);
+  }
+
+  /// Is the channel still transferring?
+  pub fn is_busy(&self) -> bool {
+    unsafe { self.reg(CH_AL1_CTRL).read_volatile() & CTRL_BUSY_BITS != 0 }
+  }
+
+  /// Number of transfers left
+  pub fn remaining(&self) -> usize {
+    unsafe { self.reg(CH_TRANS_COUNT).read_volatile() as usize }
+  }
+
+  /// Stop the channel and wait until it is idle
+  pub fn abort(&self) {
+    unsafe {
+      reg(CHAN_ABORT).write_volatile(self.mask());
+      while reg(CHAN_ABORT).read_volatile() & self.mask() != 0 {}
+    }
+  }
+
+  /// Raise `DMA_IRQ_0` once the channel completes, see [`set_irq0_callback`]
+  pub fn set_irq0_enabled(&self, enabled: bool) {
+    unsafe {
+      let inte0 = reg(INTE0);
+
+      if enabled {
+        inte0.write_volatile(inte0.read_volatile() | self.mask());
+      } else {
+        inte0.write_volatile(inte0.read_volatile() & !self.mask());
+      }
+    }
+  }
+}
+
+impl Drop for Channel {
+  fn drop(&mut self) {
+    if self.is_busy() {
+      self.abort();
+    }
+
+    self.set_irq0_enabled(false);
+    unsafe { dma_channel_unclaim(self.0) };
+  }
+}
+
+/// Start several configured channels at the same time
+pub fn start(channels: &[&Channel]) {
+  let mask = channels
+    .iter()
+    .fold(0, |mask, channel| mask | channel.mask());
+
+  unsafe { reg(MULTI_CHAN_TRIGGER).write_volatile(mask) };
+}
+
+/// Call `callback` from `DMA_IRQ_0` whenever a channel with
+/// [`Channel::set_irq0_enabled`] completes
+///
+/// The callback runs in interrupt context, keep it short.
+pub fn set_irq0_callback(callback: fn()) {
+  unsafe {
+    IRQ0_CALLBACK = Some(callback);
+    irq_set_exclusive_handler(DMA_IRQ_0, Some(dma_irq0_handler));
+    irq_set_enabled(DMA_IRQ_0, true);
+  }
+}
+
+unsafe extern "C" fn dma_irq0_handler() {
+  // Acknowledge every pending channel, the bits are write-1-to-clear
+  let status = reg(INTS0).read_volatile();
+  reg(INTS0).write_volatile(status);
+
+  if let Some(callback) = IRQ0_CALLBACK {
+    callback();
+  }
+}
@@ -1,3 +1,5 @@
+use core::fmt;
+
 /// Common return codes from pico_sdk methods that return a status
 #[allow(unused)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -13,3 +15,42 @@ pub enum PicoErrorCodes {
   CONNECT_FAILED = -8,
   INSUFFICIENT_RESOURCES = -9,
 }
+
+impl From<i32> for PicoErrorCodes {
+  /// Map a status returned by the SDK, unknown codes become `GENERIC`
+  fn from(code: i32) -> Self {
+    use PicoErrorCodes::*;
+
+    match code {
+      0 => NONE,
+      -1 => TIMEOUT,
+      -3 => NO_DATA,
+      -4 => NOT_PERMITTED,
+      -5 => INVALID_ARG,
+      -6 => IO,
+      -7 => BADAUTH,
+      -8 => CONNECT_FAILED,
+      -9 => INSUFFICIENT_RESOURCES,
+      _ => GENERIC,
+    }
+  }
+}
+
+impl fmt::Display for PicoErrorCodes {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    use PicoErrorCodes::*;
+
+    f.write_str(match self {
+      NONE => "no error",
+      TIMEOUT => "timed out",
+      GENERIC => "generic error",
+      NO_DATA => "no data available",
+      NOT_PERMITTED => "operation not permitted",
+      INVALID_ARG => "invalid argument",
+      IO => "I/O error",
+      BADAUTH => "authentication failed",
+      CONNECT_FAILED => "connection failed",
+      INSUFFICIENT_RESOURCES => "insufficient resources",
+    })
+  }
+}
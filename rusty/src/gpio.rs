@@ -1,6 +1,9 @@
 #![allow(unused)]
 
-use crate::{gpio_init, gpio_set_pulls, sio_hw_t};
+use crate::{
+  gpio_function, gpio_init, gpio_override, gpio_set_function, gpio_set_oeover, gpio_set_pulls,
+  sio_hw_t,
+};
 
 const SIO_BASE: u32 = 0xd0000000;
 const SIO_PTR: *mut sio_hw_t = SIO_BASE as _;
@@ -75,7 +78,7 @@ pub unsafe fn gpio_put_all(value: u32) {
 /// This function returns the high/low output level most recently assigned to a
 /// GPIO via [gpio_put](gpio_put) or similar. This is the value that is
 /// presented outward to the IO muxing, *not* the input level back from the pad
-/// (which can be read using [gpio_put](gpio_put)).
+/// (which can be read using [gpio_get](gpio_get)).
 ///
 /// To avoid races, this function must not be used for read-modify-write
 /// sequences when driving GPIOs -- instead functions like [gpio_put](gpio_put)
@@ -85,6 +88,11 @@ pub unsafe fn gpio_get_out_level(gpio: u32) -> bool {
   ((*SIO_PTR).gpio_out & (1 << gpio)) > 0
 }
 
+/// Get the input level of a single GPIO
+pub unsafe fn gpio_get(gpio: u32) -> bool {
+  ((*SIO_PTR).gpio_in & (1 << gpio)) > 0
+}
+
 pub unsafe fn gpio_pull_up(gpio: u32) {
   gpio_set_pulls(gpio, true, false)
 }
@@ -131,4 +139,22 @@ impl Pin {
   pub fn get(&self) -> bool {
     unsafe { gpio_get_out_level(self.0) }
   }
+
+  /// Level read back from the pad
+  pub fn read(&self) -> bool {
+    unsafe { gpio_get(self.0) }
+  }
+
+  pub fn pull_up(&self) {
+    unsafe { gpio_pull_up(self.0) }
+  }
+
+  pub fn set_function(&self, function: gpio_function) {
+    unsafe { gpio_set_function(self.0, function) }
+  }
+
+  /// Override the output enable of whichever function drives the pin
+  pub fn set_output_enable_override(&self, value: gpio_override) {
+    unsafe { gpio_set_oeover(self.0, value) }
+  }
 }
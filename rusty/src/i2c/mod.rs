@@ -0,0 +1,694 @@
+use core::cell::{Cell, RefCell, RefMut};
+use core::{fmt, mem};
+use embedded_hal::i2c::{self as hal, Operation};
+
+use crate::error::PicoErrorCodes;
+use crate::gpio::{Direction, Pin};
+use crate::std::ffi::{c_int, ToResult};
+use crate::std::thread;
+use crate::std::time::{Duration, Instant, ToAbsoluteTime};
+use crate::{
+  i2c_deinit, i2c_init, i2c_inst, i2c_read_blocking, i2c_read_blocking_until, i2c_write_blocking,
+  i2c_write_blocking_until, GPIO_FUNC_I2C,
+};
+
+pub use scan::{KnownDevice, ScanEntry, ScanResult};
+pub use target::Target;
+
+mod scan;
+pub mod target;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+  /// No device acknowledged the address
+  ///
+  /// The SDK reports every abort this way, so a lost arbitration ends up here
+  /// as well.
+  AddressNotAcknowledged,
+  /// Fewer bytes than requested were transferred
+  ///
+  /// On writes the device stopped acknowledging data after `transferred`
+  /// bytes.
+  ShortTransfer {
+    requested: usize,
+    transferred: usize,
+  },
+  Timeout,
+  /// SDA is still held low after [`I2C::recover`]
+  BusStuck,
+  /// Consecutive operations of a transaction add up to more than
+  /// [`MERGE_LEN`] bytes
+  MergeTooLong {
+    len: usize,
+  },
+  /// Any other status returned by the SDK
+  Pico(PicoErrorCodes),
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Bytes of consecutive reads or writes a transaction merges into one transfer
+pub const MERGE_LEN: usize = 64;
+
+impl hal::Error for Error {
+  fn kind(&self) -> hal::ErrorKind {
+    use hal::{ErrorKind, NoAcknowledgeSource};
+
+    match self {
+      Error::AddressNotAcknowledged => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+      Error::ShortTransfer { .. } => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data),
+      Error::BusStuck => ErrorKind::Bus,
+      Error::Timeout | Error::MergeTooLong { .. } | Error::Pico(_) => ErrorKind::Other,
+    }
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::AddressNotAcknowledged => write!(f, "address not acknowledged"),
+      Error::ShortTransfer {
+        requested,
+        transferred,
+      } => write!(f, "short transfer, {transferred} of {requested} bytes"),
+      Error::Timeout => write!(f, "timed out"),
+      Error::BusStuck => write!(f, "SDA stuck low"),
+      Error::MergeTooLong { len } => {
+        write!(f, "{len} bytes of merged operations, at most {MERGE_LEN}")
+      }
+      Error::Pico(code) => write!(f, "SDK error: {code}"),
+    }
+  }
+}
+
+pub struct I2C {
+  i2c: *mut i2c_inst,
+  /// SDA and SCL, needed to recover a stuck bus
+  pins: Option<(Pin, Pin)>,
+  /// Baudrate requested by the last [`init`](I2C::init)
+  baudrate: Cell<u32>,
+  /// Deadline of every transfer made through [`hal::I2c`]
+  timeout: Option<Duration>,
+}
+
+/// Transfers made through [`hal::I2c`] give up after a configurable time
+///
+/// Without a timeout a missing or stuck device blocks forever.
+pub trait Timeout {
+  fn timeout(&self) -> Option<Duration>;
+
+  /// Give up on every transfer of a transaction after `timeout`, `None`
+  /// blocks until it is done
+  fn set_timeout(&mut self, timeout: Option<Duration>);
+}
+
+#[allow(unused)]
+impl I2C {
+  /// Half a period of the clock used to free the bus, 100 kHz
+  const RECOVERY_DELAY: Duration = Duration::from_micros(5);
+
+  pub fn new(i2c: *mut i2c_inst) -> Self {
+    Self {
+      i2c,
+      pins: None,
+      baudrate: Cell::new(0),
+      timeout: None,
+    }
+  }
+
+  /// Use the peripheral on `sda` and `scl`, which are switched to I²C
+  pub fn with_pins(i2c: *mut i2c_inst, sda: Pin, scl: Pin) -> Self {
+    sda.set_function(GPIO_FUNC_I2C);
+    scl.set_function(GPIO_FUNC_I2C);
+    sda.pull_up();
+    scl.pull_up();
+
+    let mut i2c = Self::new(i2c);
+    i2c.pins = Some((sda, scl));
+
+    i2c
+  }
+
+  pub fn init(&self, baudrate: u32) -> u32 {
+    self.baudrate.set(baudrate);
+    unsafe { i2c_init(self.i2c, baudrate) }
+  }
+
+  /// Free a bus whose SDA line is held low by a target
+  ///
+  /// A target reset in the middle of a read keeps driving SDA until it has
+  /// shifted out the rest of its byte, which the controller sees as a busy
+  /// bus. The pins are switched to GPIO and SCL is clocked until SDA is
+  /// released, nine pulses at most, before a STOP condition ends the
+  /// transfer. The peripheral is then initialized again with the last
+  /// baudrate.
+  ///
+  /// Without pins (see [`with_pins`](I2C::with_pins)) only the peripheral is
+  /// reinitialized.
+  pub fn recover(&self) -> Result<()> {
+    unsafe { i2c_deinit(self.i2c) };
+
+    let mut released = true;
+
+    if let Some((sda, scl)) = self.pins {
+      // Lines are only ever pulled low, the pull-ups drive them high
+      sda.init();
+      scl.init();
+
+      for _ in 0..9 {
+        if sda.read() {
+          break;
+        }
+
+        scl.set_direction(Direction::OUT);
+        thread::sleep(Self::RECOVERY_DELAY);
+        scl.set_direction(Direction::IN);
+        thread::sleep(Self::RECOVERY_DELAY);
+      }
+
+      // STOP: SDA rises while SCL is high
+      scl.set_direction(Direction::OUT);
+      thread::sleep(Self::RECOVERY_DELAY);
+      sda.set_direction(Direction::OUT);
+      thread::sleep(Self::RECOVERY_DELAY);
+      scl.set_direction(Direction::IN);
+      thread::sleep(Self::RECOVERY_DELAY);
+      sda.set_direction(Direction::IN);
+      thread::sleep(Self::RECOVERY_DELAY);
+
+      released = sda.read();
+
+      sda.set_function(GPIO_FUNC_I2C);
+      scl.set_function(GPIO_FUNC_I2C);
+    }
+
+    if self.baudrate.get() > 0 {
+      self.init(self.baudrate.get());
+    }
+
+    if released {
+      Ok(())
+    } else {
+      Err(Error::BusStuck)
+    }
+  }
+
+  pub fn read_timeout<const N: usize>(
+    &self,
+    address: u8,
+    timeout: Duration,
+    stop: bool,
+  ) -> Result<[u8; N]> {
+    let mut dst: [u8; N] = [0x00; N];
+    self.read_slice_timeout(address, &mut dst, timeout, stop)?;
+
+    Ok(dst)
+  }
+
+  pub fn read<const N: usize>(&self, address: u8, stop: bool) -> Result<[u8; N]> {
+    let mut dst: [u8; N] = [0x00; N];
+    self.read_slice(address, &mut dst, stop)?;
+
+    Ok(dst)
+  }
+
+  pub fn write_timeout<const N: usize>(
+    &self,
+    address: u8,
+    src: &[u8; N],
+    timeout: Duration,
+    stop: bool,
+  ) -> Result<()> {
+    self.write_slice_timeout(address, src, timeout, stop)?;
+
+    Ok(())
+  }
+
+  pub fn write<const N: usize>(&self, address: u8, src: &[u8; N], stop: bool) -> Result<()> {
+    self.write_slice(address, src, stop)?;
+
+    Ok(())
+  }
+
+  pub fn write_read_timeout<const N: usize, const M: usize>(
+    &self,
+    address: u8,
+    src: &[u8; N],
+    timeout: Duration,
+  ) -> Result<[u8; M]> {
+    self.write_timeout(address, src, timeout, false)?;
+    self.read_timeout(address, timeout, true)
+  }
+
+  pub fn write_read<const N: usize, const M: usize>(
+    &self,
+    address: u8,
+    src: &[u8; N],
+  ) -> Result<[u8; M]> {
+    self.write(address, src, false)?;
+    self.read(address, true)
+  }
+
+  /// Read `dst.len()` bytes, giving up once `timeout` has passed
+  ///
+  /// Returns the number of bytes read.
+  pub fn read_slice_timeout(
+    &self,
+    address: u8,
+    dst: &mut [u8],
+    timeout: Duration,
+    stop: bool,
+  ) -> Result<usize> {
+    let code = unsafe {
+      i2c_read_blocking_until(
+        self.i2c,
+        address,
+        dst.as_mut_ptr(),
+        dst.len(),
+        !stop,
+        (Instant::now() + timeout).to_absolute_time(),
+      )
+    };
+
+    to_result(code, dst.len())
+  }
+
+  /// Read `dst.len()` bytes
+  ///
+  /// Returns the number of bytes read.
+  pub fn read_slice(&self, address: u8, dst: &mut [u8], stop: bool) -> Result<usize> {
+    let code = unsafe { i2c_read_blocking(self.i2c, address, dst.as_mut_ptr(), dst.len(), !stop) };
+
+    to_result(code, dst.len())
+  }
+
+  /// Write all of `src`, giving up once `timeout` has passed
+  ///
+  /// Returns the number of bytes written.
+  pub fn write_slice_timeout(
+    &self,
+    address: u8,
+    src: &[u8],
+    timeout: Duration,
+    stop: bool,
+  ) -> Result<usize> {
+    let code = unsafe {
+      i2c_write_blocking_until(
+        self.i2c,
+        address,
+        src.as_ptr(),
+        src.len(),
+        !stop,
+        (Instant::now() + timeout).to_absolute_time(),
+      )
+    };
+
+    to_result(code, src.len())
+  }
+
+  /// Write all of `src`
+  ///
+  /// Returns the number of bytes written.
+  pub fn write_slice(&self, address: u8, src: &[u8], stop: bool) -> Result<usize> {
+    let code = unsafe { i2c_write_blocking(self.i2c, address, src.as_ptr(), src.len(), !stop) };
+
+    to_result(code, src.len())
+  }
+
+  /// Write `src` then read `dst.len()` bytes after a repeated start, each part
+  /// giving up once `timeout` has passed
+  ///
+  /// Returns the number of bytes read.
+  pub fn write_read_slice_timeout(
+    &self,
+    address: u8,
+    src: &[u8],
+    dst: &mut [u8],
+    timeout: Duration,
+  ) -> Result<usize> {
+    self.write_slice_timeout(address, src, timeout, false)?;
+    self.read_slice_timeout(address, dst, timeout, true)
+  }
+
+  /// Write `src` then read `dst.len()` bytes after a repeated start
+  ///
+  /// Returns the number of bytes read.
+  pub fn write_read_slice(&self, address: u8, src: &[u8], dst: &mut [u8]) -> Result<usize> {
+    self.write_slice(address, src, false)?;
+    self.read_slice(address, dst, true)
+  }
+
+  /// Run `operations` with a repeated start between reads and writes, each
+  /// transfer giving up once `timeout` has passed
+  ///
+  /// The SDK starts every transfer with a (repeated) start and the address,
+  /// while embedded-hal sends consecutive operations of the same kind without
+  /// one. They are merged into a single transfer through a buffer of
+  /// [`MERGE_LEN`] bytes, and fail with [`Error::MergeTooLong`] beyond that.
+  fn transaction_timeout(
+    &self,
+    address: u8,
+    operations: &mut [Operation<'_>],
+    timeout: Option<Duration>,
+  ) -> Result<()> {
+    let mut rest = operations;
+
+    while let Some(first) = rest.first() {
+      let kind = mem::discriminant(first);
+      let len = rest
+        .iter()
+        .take_while(|operation| mem::discriminant(*operation) == kind)
+        .count();
+      let (group, tail) = rest.split_at_mut(len);
+
+      // Keep the bus claimed between transfers so the SDK issues a repeated
+      // start instead of a stop.
+      self.transfer(address, group, timeout, tail.is_empty())?;
+      rest = tail;
+    }
+
+    Ok(())
+  }
+
+  /// Run consecutive operations of the same kind as a single transfer
+  fn transfer(
+    &self,
+    address: u8,
+    group: &mut [Operation<'_>],
+    timeout: Option<Duration>,
+    stop: bool,
+  ) -> Result<()> {
+    let mut buffer = [0u8; MERGE_LEN];
+
+    match group {
+      [] => {}
+      [Operation::Read(dst)] => {
+        self.read_within(address, dst, timeout, stop)?;
+      }
+      [Operation::Write(src)] => {
+        self.write_within(address, src, timeout, stop)?;
+      }
+      [Operation::Read(_), ..] => {
+        let merged = merged(&mut buffer, group)?;
+        self.read_within(address, merged, timeout, stop)?;
+
+        let mut at = 0;
+        for operation in group {
+          if let Operation::Read(dst) = operation {
+            dst.copy_from_slice(&merged[at..at + dst.len()]);
+            at += dst.len();
+          }
+        }
+      }
+      [Operation::Write(_), ..] => {
+        let merged = merged(&mut buffer, group)?;
+
+        let mut at = 0;
+        for operation in group.iter() {
+          if let Operation::Write(src) = operation {
+            merged[at..at + src.len()].copy_from_slice(src);
+            at += src.len();
+          }
+        }
+
+        self.write_within(address, merged, timeout, stop)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Read `dst.len()` bytes, giving up once `timeout` has passed if any
+  fn read_within(
+    &self,
+    address: u8,
+    dst: &mut [u8],
+    timeout: Option<Duration>,
+    stop: bool,
+  ) -> Result<usize> {
+    match timeout {
+      Some(timeout) => self.read_slice_timeout(address, dst, timeout, stop),
+      None => self.read_slice(address, dst, stop),
+    }
+  }
+
+  /// Write all of `src`, giving up once `timeout` has passed if any
+  fn write_within(
+    &self,
+    address: u8,
+    src: &[u8],
+    timeout: Option<Duration>,
+    stop: bool,
+  ) -> Result<usize> {
+    match timeout {
+      Some(timeout) => self.write_slice_timeout(address, src, timeout, stop),
+      None => self.write_slice(address, src, stop),
+    }
+  }
+}
+
+impl hal::ErrorType for I2C {
+  type Error = Error;
+}
+
+impl hal::I2c for I2C {
+  fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<()> {
+    self.transaction_timeout(address, operations, self.timeout)
+  }
+}
+
+impl Timeout for I2C {
+  fn timeout(&self) -> Option<Duration> {
+    self.timeout
+  }
+
+  fn set_timeout(&mut self, timeout: Option<Duration>) {
+    self.timeout = timeout;
+  }
+}
+
+impl Drop for I2C {
+  fn drop(&mut self) {
+    unsafe {
+      i2c_deinit(self.i2c);
+    }
+  }
+}
+
+/// The start of `buffer` with room for every byte of `group`
+fn merged<'a>(buffer: &'a mut [u8; MERGE_LEN], group: &[Operation<'_>]) -> Result<&'a mut [u8]> {
+  let len = group
+    .iter()
+    .map(|operation| match operation {
+      Operation::Read(dst) => dst.len(),
+      Operation::Write(src) => src.len(),
+    })
+    .sum();
+
+  buffer.get_mut(..len).ok_or(Error::MergeTooLong { len })
+}
+
+/// Map the return value of the SDK's blocking transfer functions
+///
+/// Writes return the number of bytes sent before the device stopped
+/// acknowledging, which is reported as a short transfer.
+fn to_result(code: c_int, requested: usize) -> Result<usize> {
+  match code.to_result() {
+    Ok(transferred) if transferred < requested => Err(Error::ShortTransfer {
+      requested,
+      transferred,
+    }),
+    Ok(transferred) => Ok(transferred),
+    Err(PicoErrorCodes::GENERIC) => Err(Error::AddressNotAcknowledged),
+    Err(PicoErrorCodes::TIMEOUT) => Err(Error::Timeout),
+    Err(code) => Err(Error::Pico(code)),
+  }
+}
+
+fn reserved_addr(addr: u8) -> bool {
+  return (addr & 0x78) == 0 || (addr & 0x78) == 0x78;
+}
+
+/// Owns an I²C peripheral shared by several devices
+///
+/// The peripheral is deinitialized once the bus is dropped, which cannot
+/// happen while a [`Device`] still borrows it.
+///
+/// # Example
+///
+/// ```
+/// let bus = i2c::Bus::new(I2C::new(unsafe { addr_of_mut!(i2c0_inst) }));
+/// bus.init(400_000);
+///
+/// let imu = bus.device(0x68);
+/// let eeprom = bus.device(0x50);
+/// ```
+pub struct Bus {
+  i2c: RefCell<I2C>,
+}
+
+#[allow(unused)]
+impl Bus {
+  pub fn new(i2c: I2C) -> Self {
+    Self {
+      i2c: RefCell::new(i2c),
+    }
+  }
+
+  pub fn init(&self, baudrate: u32) -> u32 {
+    self.i2c.borrow().init(baudrate)
+  }
+
+  /// Create a handle for the device at `address`
+  pub fn device(&self, address: u8) -> Device<'_> {
+    Device::new(self, address)
+  }
+
+  /// See [`I2C::recover`]
+  pub fn recover(&self) -> Result<()> {
+    self.i2c.borrow().recover()
+  }
+
+  /// See [`I2C::scan`]
+  pub fn scan(&self) -> ScanResult {
+    self.i2c.borrow().scan()
+  }
+
+  /// Destroy the bus and return the peripheral
+  pub fn release(self) -> I2C {
+    self.i2c.into_inner()
+  }
+}
+
+/// A device at a fixed address on a shared [`Bus`]
+///
+/// Every call holds the bus exclusively until it returns, so transfers of
+/// different devices never interleave. Sequences spread over several calls
+/// with `stop == false` are not protected, use the `write_read` functions or
+/// [`transaction`](embedded_hal::i2c::I2c::transaction) instead.
+///
+/// # Panics
+///
+/// Accessing the bus while another device is using it, e.g. from an interrupt
+/// handler, panics.
+pub struct Device<'a> {
+  bus: &'a Bus,
+  address: u8,
+  timeout: Option<Duration>,
+}
+
+#[allow(unused)]
+impl<'a> Device<'a> {
+  pub fn new(bus: &'a Bus, address: u8) -> Self {
+    Self {
+      bus,
+      address,
+      timeout: None,
+    }
+  }
+
+  /// Address of the device
+  pub fn address(&self) -> u8 {
+    self.address
+  }
+
+  /// Recover the shared bus, see [`I2C::recover`]
+  pub fn recover(&self) -> Result<()> {
+    self.bus.recover()
+  }
+
+  fn i2c(&self) -> RefMut<'a, I2C> {
+    self.bus.i2c.borrow_mut()
+  }
+
+  pub fn read_timeout<const N: usize>(&self, timeout: Duration, stop: bool) -> Result<[u8; N]> {
+    self.i2c().read_timeout(self.address, timeout, stop)
+  }
+
+  pub fn read<const N: usize>(&self, stop: bool) -> Result<[u8; N]> {
+    self.i2c().read(self.address, stop)
+  }
+
+  pub fn write_timeout<const N: usize>(
+    &self,
+    src: &[u8; N],
+    timeout: Duration,
+    stop: bool,
+  ) -> Result<()> {
+    self.i2c().write_timeout(self.address, src, timeout, stop)
+  }
+
+  pub fn write<const N: usize>(&self, src: &[u8; N], stop: bool) -> Result<()> {
+    self.i2c().write(self.address, src, stop)
+  }
+
+  pub fn write_read_timeout<const N: usize, const M: usize>(
+    &self,
+    src: &[u8; N],
+    timeout: Duration,
+  ) -> Result<[u8; M]> {
+    self.i2c().write_read_timeout(self.address, src, timeout)
+  }
+
+  pub fn write_read<const N: usize, const M: usize>(&self, src: &[u8; N]) -> Result<[u8; M]> {
+    self.i2c().write_read(self.address, src)
+  }
+
+  pub fn read_slice_timeout(&self, dst: &mut [u8], timeout: Duration, stop: bool) -> Result<usize> {
+    self
+      .i2c()
+      .read_slice_timeout(self.address, dst, timeout, stop)
+  }
+
+  pub fn read_slice(&self, dst: &mut [u8], stop: bool) -> Result<usize> {
+    self.i2c().read_slice(self.address, dst, stop)
+  }
+
+  pub fn write_slice_timeout(&self, src: &[u8], timeout: Duration, stop: bool) -> Result<usize> {
+    self
+      .i2c()
+      .write_slice_timeout(self.address, src, timeout, stop)
+  }
+
+  pub fn write_slice(&self, src: &[u8], stop: bool) -> Result<usize> {
+    self.i2c().write_slice(self.address, src, stop)
+  }
+
+  pub fn write_read_slice_timeout(
+    &self,
+    src: &[u8],
+    dst: &mut [u8],
+    timeout: Duration,
+  ) -> Result<usize> {
+    self
+      .i2c()
+      .write_read_slice_timeout(self.address, src, dst, timeout)
+  }
+
+  pub fn write_read_slice(&self, src: &[u8], dst: &mut [u8]) -> Result<usize> {
+    self.i2c().write_read_slice(self.address, src, dst)
+  }
+}
+
+impl hal::ErrorType for Device<'_> {
+  type Error = Error;
+}
+
+impl hal::I2c for Device<'_> {
+  fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<()> {
+    self
+      .i2c()
+      .transaction_timeout(address, operations, self.timeout)
+  }
+}
+
+/// Each device has its own timeout, the one of the shared [`I2C`] is ignored
+impl Timeout for Device<'_> {
+  fn timeout(&self) -> Option<Duration> {
+    self.timeout
+  }
+
+  fn set_timeout(&mut self, timeout: Option<Duration>) {
+    self.timeout = timeout;
+  }
+}
@@ -0,0 +1,225 @@
+use core::fmt;
+
+use super::{reserved_addr, I2C};
+use crate::std::time::Duration;
+
+/// Device recognized by its identification register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KnownDevice {
+  Icm42688P,
+  Icm42605,
+  Icm20602,
+  Mpu6050,
+  Mpu6500,
+  Mpu9250,
+}
+
+impl KnownDevice {
+  pub fn name(&self) -> &'static str {
+    match self {
+      KnownDevice::Icm42688P => "ICM-42688-P",
+      KnownDevice::Icm42605 => "ICM-42605",
+      KnownDevice::Icm20602 => "ICM-20602",
+      KnownDevice::Mpu6050 => "MPU-6050",
+      KnownDevice::Mpu6500 => "MPU-6500",
+      KnownDevice::Mpu9250 => "MPU-9250",
+    }
+  }
+}
+
+impl fmt::Display for KnownDevice {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.name())
+  }
+}
+
+/// Identification register read at a set of addresses
+struct Probe {
+  addresses: &'static [u8],
+  register: u8,
+  ids: &'static [(u8, KnownDevice)],
+}
+
+const PROBES: &[Probe] = &[
+  // InvenSense parts answer at 0x68 or 0x69, depending on `AP_AD0`
+  Probe {
+    addresses: &[0x68, 0x69],
+    register: 0x75,
+    ids: &[
+      (0x47, KnownDevice::Icm42688P),
+      (0x42, KnownDevice::Icm42605),
+      (0x12, KnownDevice::Icm20602),
+      (0x68, KnownDevice::Mpu6050),
+      (0x70, KnownDevice::Mpu6500),
+      (0x71, KnownDevice::Mpu9250),
+    ],
+  },
+];
+
+/// An address which acknowledged during a [scan](I2C::scan)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ScanEntry {
+  pub address: u8,
+  /// Value of the identification register, if the address has a probe
+  pub id: Option<u8>,
+  /// Device the identification value belongs to
+  pub device: Option<KnownDevice>,
+}
+
+impl fmt::Display for ScanEntry {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:#04x}", self.address)?;
+
+    match (self.device, self.id) {
+      (Some(device), _) => write!(f, " {device}"),
+      (None, Some(id)) => write!(f, " unknown (id {id:#04x})"),
+      (None, None) => Ok(()),
+    }
+  }
+}
+
+/// Addresses found by [`I2C::scan`], in ascending order
+#[derive(Debug, Clone)]
+pub struct ScanResult {
+  entries: [ScanEntry; ScanResult::CAPACITY],
+  len: usize,
+}
+
+#[allow(unused)]
+impl ScanResult {
+  /// Number of addresses outside the reserved ranges
+  pub const CAPACITY: usize = 112;
+
+  fn new() -> Self {
+    Self {
+      entries: [ScanEntry::default(); Self::CAPACITY],
+      len: 0,
+    }
+  }
+
+  fn push(&mut self, entry: ScanEntry) {
+    self.entries[self.len] = entry;
+    self.len += 1;
+  }
+
+  pub fn entries(&self) -> &[ScanEntry] {
+    &self.entries[..self.len]
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &ScanEntry> {
+    self.entries().iter()
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Entry of `address`, if it acknowledged
+  pub fn get(&self, address: u8) -> Option<&ScanEntry> {
+    self.iter().find(|entry| entry.address == address)
+  }
+
+  pub fn contains(&self, address: u8) -> bool {
+    self.get(address).is_some()
+  }
+
+  /// Format the result as a table of all addresses, marking the ones that
+  /// acknowledged with `@`
+  pub fn table(&self) -> Table<'_> {
+    Table(self)
+  }
+}
+
+/// Lists every entry on its own line
+impl fmt::Display for ScanResult {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for entry in self.iter() {
+      writeln!(f, "{entry}")?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Table formatter of a [`ScanResult`], see [`ScanResult::table`]
+pub struct Table<'a>(&'a ScanResult);
+
+impl fmt::Display for Table<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "   0  1  2  3  4  5  6  7  8  9  A  B  C  D  E  F")?;
+
+    for addr in 0..(1u8 << 7) {
+      if addr % 16 == 0 {
+        write!(f, "{:X} ", addr / 16)?;
+      }
+
+      write!(f, "{}", if self.0.contains(addr) { "@" } else { "." })?;
+
+      if addr % 16 == 15 {
+        writeln!(f)?;
+      } else {
+        write!(f, "  ")?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[allow(unused)]
+impl I2C {
+  /// Time each address gets to respond, so a stuck bus cannot hang the scan
+  const SCAN_TIMEOUT: Duration = Duration::from_millis(10);
+
+  /// Find every address which acknowledges a read
+  ///
+  /// Addresses with a known identification register are probed, e.g.
+  /// `WHO_AM_I` of InvenSense parts at 0x68/0x69, to label the entry with the
+  /// likely device.
+  pub fn scan(&self) -> ScanResult {
+    let mut result = ScanResult::new();
+
+    for address in (0..(1 << 7)).filter(|addr| !reserved_addr(*addr)) {
+      let mut byte = [0u8; 1];
+
+      if self
+        .read_slice_timeout(address, &mut byte, Self::SCAN_TIMEOUT, true)
+        .is_err()
+      {
+        continue;
+      }
+
+      let mut entry = ScanEntry {
+        address,
+        ..Default::default()
+      };
+
+      if let Some(probe) = PROBES.iter().find(|p| p.addresses.contains(&address)) {
+        entry.id = self.probe(address, probe.register);
+        entry.device = probe
+          .ids
+          .iter()
+          .find(|(id, _)| Some(*id) == entry.id)
+          .map(|(_, device)| *device);
+      }
+
+      result.push(entry);
+    }
+
+    result
+  }
+
+  /// Read a single identification register
+  fn probe(&self, address: u8, register: u8) -> Option<u8> {
+    let mut id = [0u8; 1];
+
+    self
+      .write_read_slice_timeout(address, &[register], &mut id, Self::SCAN_TIMEOUT)
+      .ok()
+      .map(|_| id[0])
+  }
+}
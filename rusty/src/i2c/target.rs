@@ -0,0 +1,240 @@
+use core::cell::RefCell;
+use core::ptr::addr_of_mut;
+use cortex_m::interrupt::{self, Mutex};
+
+use super::I2C;
+use crate::{
+  i2c0_inst, i2c_inst, i2c_slave_deinit, i2c_slave_event_t, i2c_slave_init, I2C_SLAVE_FINISH,
+  I2C_SLAVE_RECEIVE, I2C_SLAVE_REQUEST,
+};
+
+const IC_DATA_CMD: usize = 0x10;
+const IC_RXFLR: usize = 0x78;
+
+/// Number of bytes in the register map
+const LEN: usize = 0x20;
+
+pub const STATUS: u8 = 0x00;
+pub const SAMPLE: u8 = 0x01;
+pub const SAMPLE_LEN: usize = 14;
+pub const COUNTER: u8 = 0x0f;
+pub const CONFIG: u8 = 0x10;
+pub const CONFIG_LEN: usize = 16;
+
+/// `STATUS`: a sample was published since `STATUS` was last read
+pub const DATA_READY: u8 = 1 << 0;
+/// `STATUS`: the firmware reports a fault, see [`Target::set_fault`]
+pub const FAULT: u8 = 1 << 1;
+/// `STATUS`: the configuration window was written by the controller
+pub const CONFIG_CHANGED: u8 = 1 << 2;
+
+/// The peripheral answering as an I²C target with a small register map
+///
+/// | Address       | Access | Content                                               |
+/// |---------------|--------|-------------------------------------------------------|
+/// | `0x00`        | R      | [`DATA_READY`], [`FAULT`] and [`CONFIG_CHANGED`] flags |
+/// | `0x01..=0x0e` | R      | latest published sample                               |
+/// | `0x0f`        | R      | number of published samples, wraps around             |
+/// | `0x10..=0x1f` | R/W    | configuration window                                  |
+///
+/// The controller writes the register address first, then reads or writes
+/// data, which auto-increments the address. A read serves a copy of the map
+/// taken at its start, so a sample published meanwhile cannot tear.
+///
+/// Controller mode is restored once the target is dropped.
+///
+/// # Example
+///
+/// ```
+/// let mut i2c = I2C::with_pins(unsafe { addr_of_mut!(i2c1_inst) }, sda, scl);
+/// i2c.init(400_000);
+///
+/// let target = i2c.target(0x42);
+/// target.publish(&icm.sample_raw()?.to_bytes());
+/// ```
+pub struct Target<'a> {
+  i2c: &'a mut I2C,
+  index: usize,
+}
+
+#[allow(unused)]
+impl Target<'_> {
+  /// Make `sample` the latest data served to the controller
+  ///
+  /// The bytes are served as they are, e.g. an IMU sample laid out like
+  /// `TEMP_DATA1` onwards.
+  pub fn publish(&self, sample: &[u8; SAMPLE_LEN]) {
+    self.with_registers(|regs| {
+      let start = SAMPLE as usize;
+      regs.map[start..start + SAMPLE_LEN].copy_from_slice(sample);
+      regs.map[COUNTER as usize] = regs.map[COUNTER as usize].wrapping_add(1);
+      regs.map[STATUS as usize] |= DATA_READY;
+    })
+  }
+
+  /// Report a fault to the controller, e.g. when the sensor stopped answering
+  pub fn set_fault(&self, fault: bool) {
+    self.with_registers(|regs| {
+      if fault {
+        regs.map[STATUS as usize] |= FAULT;
+      } else {
+        regs.map[STATUS as usize] &= !FAULT;
+      }
+    })
+  }
+
+  /// Return the configuration window
+  pub fn config(&self) -> [u8; CONFIG_LEN] {
+    self.with_registers(|regs| {
+      let mut config = [0u8; CONFIG_LEN];
+      config.copy_from_slice(&regs.map[config_range()]);
+
+      config
+    })
+  }
+
+  /// Set the configuration window, e.g. to the defaults at start-up
+  pub fn set_config(&self, config: &[u8; CONFIG_LEN]) {
+    self.with_registers(|regs| regs.map[config_range()].copy_from_slice(config))
+  }
+
+  /// Return the configuration window if the controller wrote it since the
+  /// last call
+  pub fn take_config(&self) -> Option<[u8; CONFIG_LEN]> {
+    let changed = self.with_registers(|regs| {
+      let status = &mut regs.map[STATUS as usize];
+      let changed = *status & CONFIG_CHANGED != 0;
+      *status &= !CONFIG_CHANGED;
+
+      changed
+    });
+
+    changed.then(|| self.config())
+  }
+
+  fn with_registers<T>(&self, f: impl FnOnce(&mut Registers) -> T) -> T {
+    interrupt::free(|cs| f(&mut REGISTERS[self.index].borrow(cs).borrow_mut()))
+  }
+}
+
+impl Drop for Target<'_> {
+  fn drop(&mut self) {
+    unsafe { i2c_slave_deinit(self.i2c.i2c) }
+  }
+}
+
+#[allow(unused)]
+impl I2C {
+  /// Answer as a target at `address` instead of driving the bus
+  ///
+  /// The peripheral has to be initialized, the baudrate does not matter as
+  /// the controller provides the clock.
+  pub fn target(&mut self, address: u8) -> Target<'_> {
+    let index = index(self.i2c);
+
+    interrupt::free(|cs| *REGISTERS[index].borrow(cs).borrow_mut() = Registers::new());
+    unsafe { i2c_slave_init(self.i2c, address, Some(handler)) };
+
+    Target { i2c: self, index }
+  }
+}
+
+fn config_range() -> core::ops::Range<usize> {
+  CONFIG as usize..CONFIG as usize + CONFIG_LEN
+}
+
+/// State of the register map shared with the interrupt handler
+struct Registers {
+  map: [u8; LEN],
+  /// Copy of `map` served during a read
+  latch: [u8; LEN],
+  pointer: usize,
+  /// The first byte written after a start sets the pointer
+  addressed: bool,
+  latched: bool,
+}
+
+impl Registers {
+  const fn new() -> Self {
+    Self {
+      map: [0; LEN],
+      latch: [0; LEN],
+      pointer: 0,
+      addressed: false,
+      latched: false,
+    }
+  }
+
+  fn receive(&mut self, byte: u8) {
+    if !self.addressed {
+      self.pointer = byte as usize % LEN;
+      self.addressed = true;
+      return;
+    }
+
+    // Everything outside the configuration window is read-only
+    if config_range().contains(&self.pointer) {
+      self.map[self.pointer] = byte;
+      self.map[STATUS as usize] |= CONFIG_CHANGED;
+    }
+
+    self.pointer = (self.pointer + 1) % LEN;
+  }
+
+  fn request(&mut self) -> u8 {
+    if !self.latched {
+      self.latch = self.map;
+      self.latched = true;
+    }
+
+    if self.pointer == STATUS as usize {
+      self.map[self.pointer] &= !DATA_READY;
+    }
+
+    let byte = self.latch[self.pointer];
+    self.pointer = (self.pointer + 1) % LEN;
+
+    byte
+  }
+
+  fn finish(&mut self) {
+    self.addressed = false;
+    self.latched = false;
+  }
+}
+
+static REGISTERS: [Mutex<RefCell<Registers>>; 2] = [
+  Mutex::new(RefCell::new(Registers::new())),
+  Mutex::new(RefCell::new(Registers::new())),
+];
+
+/// Called by the SDK from the I²C interrupt
+unsafe extern "C" fn handler(i2c: *mut i2c_inst, event: i2c_slave_event_t) {
+  interrupt::free(|cs| {
+    let mut regs = REGISTERS[index(i2c)].borrow(cs).borrow_mut();
+
+    match event {
+      I2C_SLAVE_RECEIVE => {
+        while reg(i2c, IC_RXFLR).read_volatile() > 0 {
+          regs.receive(reg(i2c, IC_DATA_CMD).read_volatile() as u8);
+        }
+      }
+      I2C_SLAVE_REQUEST => reg(i2c, IC_DATA_CMD).write_volatile(regs.request() as u32),
+      I2C_SLAVE_FINISH => regs.finish(),
+      _ => {}
+    }
+  })
+}
+
+fn index(i2c: *mut i2c_inst) -> usize {
+  if i2c == unsafe { addr_of_mut!(i2c0_inst) } {
+    0
+  } else {
+    1
+  }
+}
+
+/// Pointer to a register of the peripheral
+unsafe fn reg(i2c: *mut i2c_inst, offset: usize) -> *mut u32 {
+  ((*i2c).hw as *mut u8).add(offset) as *mut u32
+}
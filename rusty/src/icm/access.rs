@@ -0,0 +1,122 @@
+// The parts of a register access which do not touch the bus, shared by the
+// blocking and the async driver. The drivers only move the bytes, and ask this
+// module what to send and how to judge what came back.
+
+use super::config::WriteVerify;
+use super::error::SensorError;
+use super::register::{bank0, Access, Bank0, Register, RegisterBank};
+
+type Result<T> = core::result::Result<T, SensorError>;
+
+/// Bank selected in `REG_BANK_SEL`
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct BankSelect {
+  /// `None` until the driver selected a bank
+  bank: Option<RegisterBank>,
+}
+
+impl BankSelect {
+  /// Bytes pointing `REG_BANK_SEL` at `bank`, `None` if it is selected
+  /// already
+  pub fn select(&self, bank: RegisterBank) -> Option<[u8; 2]> {
+    (self.bank != Some(bank)).then(|| {
+      [
+        Bank0::REG_BANK_SEL.addr(),
+        bank0::REG_BANK_SEL::BANK_SEL.set(0x00, bank.blk_sel()),
+      ]
+    })
+  }
+
+  /// Record the outcome of writing the bytes returned by [`select`](Self::select)
+  ///
+  /// After a failed write the selected bank is unknown, and the next access
+  /// selects it again.
+  pub fn selected(&mut self, bank: RegisterBank, ok: bool) {
+    self.bank = ok.then_some(bank);
+  }
+
+  /// Forget the selected bank, e.g. when something else may have changed it
+  pub fn invalidate(&mut self) {
+    self.bank = None;
+  }
+
+  /// A reset selects bank 0
+  pub fn reset(&mut self) {
+    self.bank = Some(RegisterBank::Bank0);
+  }
+}
+
+/// Fail for registers which cannot be read
+pub(super) fn check_readable(reg: &dyn Register) -> Result<()> {
+  match reg.access() {
+    Access::WriteOnly => Err(SensorError::ReadFromWriteOnly),
+    _ => Ok(()),
+  }
+}
+
+/// Fail for registers which cannot be written
+pub(super) fn check_writable(reg: &dyn Register) -> Result<()> {
+  if reg.read_only() {
+    Err(SensorError::WriteToReadOnly)
+  } else {
+    Ok(())
+  }
+}
+
+/// Replace the bits of `current` selected by `mask` with the ones of `value`
+pub(super) fn merge_bits(current: u8, value: u8, mask: u8) -> u8 {
+  (current & !mask) | (value & mask)
+}
+
+/// Read-back of a verified register write
+pub(super) struct WriteCheck<'a> {
+  reg: &'a dyn Register,
+  value: u8,
+  retries: u8,
+  attempt: u8,
+}
+
+/// What to do after reading back a written register
+pub(super) enum Verdict {
+  /// The register holds the written value
+  Done,
+  /// Write the value again and read it back once more
+  Retry,
+  /// Out of retries
+  Failed(SensorError),
+}
+
+impl<'a> WriteCheck<'a> {
+  /// Check writing `value` to `reg`, `None` if writes are not verified
+  pub fn new(reg: &'a dyn Register, value: u8, verify: WriteVerify) -> Option<Self> {
+    match verify {
+      WriteVerify::Disabled => None,
+      WriteVerify::Enabled { retries } => Some(Self {
+        reg,
+        value,
+        retries,
+        attempt: 0,
+      }),
+    }
+  }
+
+  /// Compare the value read back, ignoring the self-clearing bits
+  pub fn check(&mut self, actual: u8) -> Verdict {
+    let mask = !self.reg.self_clearing();
+    let expected = self.value & mask;
+    let actual = actual & mask;
+
+    if actual == expected {
+      Verdict::Done
+    } else if self.attempt == self.retries {
+      Verdict::Failed(SensorError::WriteVerifyFailed {
+        reg: self.reg.addr(),
+        expected,
+        actual,
+      })
+    } else {
+      self.attempt += 1;
+      Verdict::Retry
+    }
+  }
+}
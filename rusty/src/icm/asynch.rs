@@ -0,0 +1,618 @@
+use accelerometer::vector::{F32x3, I16x3};
+use core::marker::PhantomData;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::digital::Wait;
+use embedded_hal_async::{i2c::I2c, spi::SpiDevice};
+
+use super::access::{check_readable, check_writable, merge_bits, BankSelect, Verdict, WriteCheck};
+use super::config::*;
+use super::error::{BusErrorKind, Error, SensorError, TransitionError};
+use super::register::{bank0, Bank0, Bank1, Register, RegisterBank, TypedRegister, Update, Value};
+use super::state::{Configured, Ready, State, Streaming, Unconfigured};
+use super::{
+  celsius, data_ready_interrupt, normalize, I2cInterface, ImuSample, Result, SpiInterface,
+  Transition, DEVICE_ID, FIFO_START, FIFO_STOP,
+};
+
+/// Bus abstraction used by the async driver to talk to the sensor
+#[allow(async_fn_in_trait)]
+pub trait Interface {
+  type Error: core::fmt::Debug;
+
+  /// Broad kind of an error of this bus
+  fn error_kind(err: &Self::Error) -> BusErrorKind;
+
+  /// Write raw bytes to the device, starting with the register address
+  async fn write(&mut self, bytes: &[u8]) -> core::result::Result<(), Self::Error>;
+
+  /// Read `buffer.len()` consecutive registers starting at `addr`
+  async fn read_registers(
+    &mut self,
+    addr: u8,
+    buffer: &mut [u8],
+  ) -> core::result::Result<(), Self::Error>;
+}
+
+/// Async ICM-42688 driver
+///
+/// Has the configuration and data API of the blocking
+/// [`ICM42688`](super::ICM42688), including its lifecycle states, and decodes
+/// registers the same way. Retries and bus recovery are left to the bus
+/// implementation.
+///
+/// The shadow cache, [snapshots](super::Snapshot) and their restore, the reset
+/// value check and the link test are only available in the blocking driver.
+///
+/// # Example
+///
+/// ```
+/// let mut icm = asynch::ICM42688::with_spi(spi, delay).init().await?;
+/// icm.set_data_ready_interrupt(true).await?;
+///
+/// loop {
+///   icm.wait_for_data_ready(&mut int1).await?;
+///   let sample = icm.sample_raw().await?;
+/// }
+/// ```
+pub struct ICM42688<IFACE, D, STATE = Unconfigured> {
+  iface: IFACE,
+  delay: D,
+  write_verify: WriteVerify,
+  bank: BankSelect,
+  state: PhantomData<STATE>,
+}
+
+impl<I, D> ICM42688<I2cInterface<I>, D>
+where
+  I: I2c,
+  D: DelayNs,
+{
+  pub fn with_i2c(i2c: I, address: Address, delay: D) -> Self {
+    Self::new(I2cInterface::new(i2c, address as _), delay)
+  }
+}
+
+impl<S, D> ICM42688<SpiInterface<S>, D>
+where
+  S: SpiDevice,
+  D: DelayNs,
+{
+  pub fn with_spi(spi: S, delay: D) -> Self {
+    Self::new(SpiInterface::new(spi), delay)
+  }
+}
+
+impl<IFACE: Interface, D: DelayNs> ICM42688<IFACE, D> {
+  pub fn new(iface: IFACE, delay: D) -> Self {
+    Self {
+      iface,
+      delay,
+      write_verify: WriteVerify::default(),
+      bank: BankSelect::default(),
+      state: PhantomData,
+    }
+  }
+
+  /// Check the device, reset it and restore the default configuration
+  ///
+  /// Hands back the unconfigured driver on failure.
+  pub async fn init(mut self) -> Transition<Self, ICM42688<IFACE, D, Configured>, IFACE::Error> {
+    match self.configure().await {
+      Ok(()) => Ok(self.into_state()),
+      Err(error) => Err(TransitionError {
+        driver: self,
+        error,
+      }),
+    }
+  }
+
+  async fn configure(&mut self) -> Result<(), IFACE::Error> {
+    if self.device_id().await? != DEVICE_ID {
+      return Err(Error::SensorError(SensorError::BadChip));
+    }
+
+    self.reset().await?;
+
+    // Restore the default ranges and enable both the accel and gyro, like the
+    // blocking driver
+    self.set_setting(AccelRange::default()).await?;
+    self.set_setting(GyroRange::default()).await?;
+    self.set_setting(PowerMode::SixAxisLowNoise).await
+  }
+}
+
+#[allow(unused)]
+impl<IFACE: Interface, D: DelayNs, STATE: State> ICM42688<IFACE, D, STATE> {
+  /// Return how register writes are verified
+  pub fn write_verify(&self) -> WriteVerify {
+    self.write_verify
+  }
+
+  /// Set how register writes are verified
+  pub fn set_write_verify(&mut self, verify: WriteVerify) {
+    self.write_verify = verify;
+  }
+
+  pub fn interface(&self) -> &IFACE {
+    &self.iface
+  }
+
+  pub fn interface_mut(&mut self) -> &mut IFACE {
+    &mut self.iface
+  }
+
+  /// Destroy the driver and return the underlying interface and delay
+  ///
+  /// Bank 0 is selected again on the way out, as other code talking to the
+  /// sensor expects it. This is best effort, a failed write is ignored.
+  pub async fn release(mut self) -> (IFACE, D) {
+    let _ = self.select_bank(RegisterBank::Bank0).await;
+
+    (self.iface, self.delay)
+  }
+
+  /// Forget which register bank is selected, the next access selects it again
+  ///
+  /// The driver only writes `REG_BANK_SEL` when switching banks. Anything
+  /// resetting the sensor behind the driver's back, like a brown-out, leaves
+  /// it pointing at the wrong bank until this is called.
+  pub fn invalidate_bank(&mut self) {
+    self.bank.invalidate();
+  }
+
+  /// Read the ID of the connected device
+  pub async fn device_id(&mut self) -> Result<u8, IFACE::Error> {
+    self.read_register(&Bank0::WHO_AM_I).await
+  }
+
+  /// soft reset the device
+  ///
+  /// Every register returns to its reset value, so the driver has to be
+  /// initialized again. Hands back the driver in its current state on failure.
+  pub async fn soft_reset(
+    mut self,
+  ) -> Transition<Self, ICM42688<IFACE, D, Unconfigured>, IFACE::Error> {
+    match self.reset().await {
+      Ok(()) => Ok(self.into_state()),
+      Err(error) => Err(TransitionError {
+        driver: self,
+        error,
+      }),
+    }
+  }
+
+  pub async fn set_i2c_slew_rate(&mut self, slew_rate: I2CSlewRate) -> Result<(), IFACE::Error> {
+    self
+      .modify_reg(|value: &mut Value<bank0::DRIVE_CONFIG>| {
+        value.set(bank0::DRIVE_CONFIG::I2C_SLEW_RATE, slew_rate as u8);
+      })
+      .await
+  }
+
+  /// Return how the sensor is connected to the host
+  pub async fn interface_config(&mut self) -> Result<InterfaceConfig, IFACE::Error> {
+    let intf_config0 = self.read_reg().await?;
+    let intf_config4 = self.read_reg().await?;
+
+    Ok(InterfaceConfig::decode(intf_config0, intf_config4)?)
+  }
+
+  /// Configure the serial interfaces of the sensor, see
+  /// [`ICM42688::set_interface_config`](super::ICM42688::set_interface_config)
+  pub async fn set_interface_config(
+    &mut self,
+    config: InterfaceConfig,
+  ) -> Result<(), IFACE::Error> {
+    let mut intf_config4 = self.read_reg().await?;
+    let mut intf_config0 = self.read_reg().await?;
+    config.encode(&mut intf_config0, &mut intf_config4);
+
+    // Only writes from here on, reads fail until the host switched over too
+    self
+      .send_register(&Bank0::INTF_CONFIG0, intf_config0.bits())
+      .await?;
+    self
+      .send_register(&Bank1::INTF_CONFIG4, intf_config4.bits())
+      .await
+  }
+}
+
+impl<IFACE: Interface, D: DelayNs, STATE: Ready> ICM42688<IFACE, D, STATE> {
+  /// Return the normalized gyro data for each of the three axes
+  pub async fn gyro_norm(&mut self) -> Result<F32x3, IFACE::Error> {
+    let range = self.gyro_range().await?;
+
+    Ok(normalize(self.gyro_raw().await?, range.scale_factor()))
+  }
+
+  /// Read the raw gyro data for each of the three axes
+  pub async fn gyro_raw(&mut self) -> Result<I16x3, IFACE::Error> {
+    Ok(self.sample_raw().await?.gyro)
+  }
+
+  /// Return the normalized accelerometer data for each of the three axes
+  pub async fn accel_norm(&mut self) -> Result<F32x3, IFACE::Error> {
+    let range = self.accel_range().await?;
+
+    Ok(normalize(self.accel_raw().await?, range.scale_factor()))
+  }
+
+  /// Read the raw accelerometer data for each of the three axes
+  pub async fn accel_raw(&mut self) -> Result<I16x3, IFACE::Error> {
+    Ok(self.sample_raw().await?.accel)
+  }
+
+  /// Return the output data rate of the accelerometer in Hz
+  pub async fn sample_rate(&mut self) -> Result<f32, IFACE::Error> {
+    Ok(self.accel_odr().await?.as_f32())
+  }
+
+  /// Read temperature, accelerometer and gyroscope data in a single burst
+  pub async fn sample_raw(&mut self) -> Result<ImuSample, IFACE::Error> {
+    let mut frame = [0u8; ImuSample::LEN];
+    self.read_registers(&Bank0::TEMP_DATA1, &mut frame).await?;
+
+    Ok(ImuSample::from_bytes(&frame))
+  }
+
+  /// Read the built-in temperature sensor and return the value in degrees
+  /// centigrade
+  pub async fn temperature(&mut self) -> Result<f32, IFACE::Error> {
+    Ok(celsius(self.temperature_raw().await?))
+  }
+
+  /// Read the raw data from the built-in temperature sensor
+  pub async fn temperature_raw(&mut self) -> Result<i16, IFACE::Error> {
+    Ok(self.sample_raw().await?.temperature)
+  }
+
+  /// Return the currently configured power mode
+  pub async fn power_mode(&mut self) -> Result<PowerMode, IFACE::Error> {
+    self.setting().await
+  }
+
+  /// Set the power mode of the IMU
+  pub async fn set_power_mode(&mut self, mode: PowerMode) -> Result<(), IFACE::Error> {
+    self.set_setting(mode).await
+  }
+
+  /// Return the currently configured accelerometer range
+  pub async fn accel_range(&mut self) -> Result<AccelRange, IFACE::Error> {
+    self.setting().await
+  }
+
+  /// Set the range of the accelerometer
+  pub async fn set_accel_range(&mut self, range: AccelRange) -> Result<(), IFACE::Error> {
+    self.set_setting(range).await
+  }
+
+  /// Return the currently configured gyroscope range
+  pub async fn gyro_range(&mut self) -> Result<GyroRange, IFACE::Error> {
+    self.setting().await
+  }
+
+  /// Set the range of the gyro
+  pub async fn set_gyro_range(&mut self, range: GyroRange) -> Result<(), IFACE::Error> {
+    self.set_setting(range).await
+  }
+
+  /// Return the currently configured output data rate for the gyroscope
+  pub async fn gyro_odr(&mut self) -> Result<GyroODR, IFACE::Error> {
+    self.setting().await
+  }
+
+  /// Set the output data rate of the gyroscope
+  pub async fn set_gyro_odr(&mut self, odr: GyroODR) -> Result<(), IFACE::Error> {
+    self.set_setting(odr).await
+  }
+
+  pub async fn gyro_bandwith(&mut self) -> Result<GyroBandwidth, IFACE::Error> {
+    self.setting().await
+  }
+
+  /// Set the gyro_bandwith filter of the gyro
+  pub async fn set_gyro_bw(&mut self, range: GyroBandwidth) -> Result<(), IFACE::Error> {
+    self.set_setting(range).await
+  }
+
+  /// Return the currently configured output data rate for the accelerometer
+  pub async fn accel_odr(&mut self) -> Result<AccelODR, IFACE::Error> {
+    self.setting().await
+  }
+
+  /// Set the output data rate of the accelerometer
+  pub async fn set_accel_odr(&mut self, odr: AccelODR) -> Result<(), IFACE::Error> {
+    self.set_setting(odr).await
+  }
+
+  pub async fn accel_bandwith(&mut self) -> Result<AccelBandwidth, IFACE::Error> {
+    self.setting().await
+  }
+
+  /// Set the accel_bandwith filter of the accel-meter
+  pub async fn set_accel_bw(&mut self, range: AccelBandwidth) -> Result<(), IFACE::Error> {
+    self.set_setting(range).await
+  }
+
+  /// Route the data ready interrupt to the `INT1` pin, push-pull and active
+  /// high
+  ///
+  /// The pin is latched, it stays high until `INT_STATUS` is read.
+  pub async fn set_data_ready_interrupt(&mut self, enabled: bool) -> Result<(), IFACE::Error> {
+    self.update_registers(&data_ready_interrupt(enabled)).await
+  }
+
+  /// Check whether a new sample is available
+  ///
+  /// Reading `INT_STATUS` clears all of its interrupt flags.
+  pub async fn data_ready(&mut self) -> Result<bool, IFACE::Error> {
+    let status = self.read_reg::<bank0::INT_STATUS>().await?;
+
+    Ok(status.get(bank0::INT_STATUS::DATA_RDY_INT) != 0)
+  }
+
+  /// Wait until a new sample is available
+  ///
+  /// `int1` is the pin wired to `INT1`, which has to be enabled with
+  /// [`set_data_ready_interrupt`](Self::set_data_ready_interrupt). Returns
+  /// right away if a sample arrived since `INT_STATUS` was last read, and
+  /// keeps waiting while other interrupt sources raise the pin.
+  ///
+  /// The latched pin stays high for a sample arriving between reading
+  /// `INT_STATUS` and waiting on the pin, so it cannot be missed.
+  pub async fn wait_for_data_ready<P: Wait>(&mut self, int1: &mut P) -> Result<(), IFACE::Error> {
+    while !self.data_ready().await? {
+      int1
+        .wait_for_high()
+        .await
+        .map_err(|_| Error::PinError)?;
+    }
+
+    Ok(())
+  }
+
+  /// read time stampe from register
+  pub async fn read_tmst(&mut self) -> Result<u16, IFACE::Error> {
+    self
+      .read_register_u16(&Bank0::TMST_FSYNCH, &Bank0::TMST_FSYNCL)
+      .await
+  }
+}
+
+impl<IFACE: Interface, D: DelayNs> ICM42688<IFACE, D, Configured> {
+  /// Start the FIFO in stream mode, recording temperature, accelerometer and
+  /// gyroscope data
+  ///
+  /// Hands back the configured driver on failure.
+  pub async fn start_fifo(
+    mut self,
+  ) -> Transition<Self, ICM42688<IFACE, D, Streaming>, IFACE::Error> {
+    match self.update_registers(&FIFO_START).await {
+      Ok(()) => Ok(self.into_state()),
+      Err(error) => Err(TransitionError {
+        driver: self,
+        error,
+      }),
+    }
+  }
+}
+
+impl<IFACE: Interface, D: DelayNs> ICM42688<IFACE, D, Streaming> {
+  /// read current fifo buffer level, available to read
+  pub async fn read_fifo_cnt(&mut self) -> Result<u16, IFACE::Error> {
+    self
+      .read_register_u16(&Bank0::FIFO_COUNTH, &Bank0::FIFO_COUNTL)
+      .await
+  }
+
+  pub async fn read_fifo(&mut self, addr: u8) -> Result<u8, IFACE::Error> {
+    let mut buffer = [0u8; 8];
+    self.select_bank(RegisterBank::Bank0).await?;
+    self
+      .iface
+      .read_registers(addr, &mut buffer)
+      .await
+      .map_err(Error::BusError)?;
+
+    Ok(buffer[0])
+  }
+
+  /// Drain up to `buffer.len()` bytes from the FIFO in a single burst
+  ///
+  /// Returns the number of bytes read, which is the smaller of the buffer length
+  /// and the current FIFO level.
+  pub async fn read_fifo_data(&mut self, buffer: &mut [u8]) -> Result<usize, IFACE::Error> {
+    let len = buffer.len().min(self.read_fifo_cnt().await? as usize);
+    self
+      .read_registers(&Bank0::FIFO_DATA, &mut buffer[..len])
+      .await?;
+
+    Ok(len)
+  }
+
+  /// Put the FIFO back into bypass mode
+  ///
+  /// Hands back the streaming driver on failure.
+  pub async fn stop_fifo(
+    mut self,
+  ) -> Transition<Self, ICM42688<IFACE, D, Configured>, IFACE::Error> {
+    match self.update_registers(&FIFO_STOP).await {
+      Ok(()) => Ok(self.into_state()),
+      Err(error) => Err(TransitionError {
+        driver: self,
+        error,
+      }),
+    }
+  }
+}
+
+#[allow(unused)]
+impl<IFACE: Interface, D: DelayNs, STATE: State> ICM42688<IFACE, D, STATE> {
+  /// Write `SOFT_RESET_CONFIG` and wait for the device to come back
+  async fn reset(&mut self) -> Result<(), IFACE::Error> {
+    // The device does not respond for 1ms after a soft reset, so the write
+    // cannot be read back.
+    let mut config = self.read_reg::<bank0::DEVICE_CONFIG>().await?;
+    config.set(bank0::DEVICE_CONFIG::SOFT_RESET_CONFIG, 1);
+    self
+      .send_register(&Bank0::DEVICE_CONFIG, config.bits())
+      .await?;
+    self.delay.delay_ms(1).await;
+
+    // `REG_BANK_SEL` is reset as well
+    self.bank.reset();
+
+    Ok(())
+  }
+
+  /// Apply a list of masked updates
+  async fn update_registers(&mut self, updates: &[Update]) -> Result<(), IFACE::Error> {
+    for update in updates {
+      self
+        .update_register(&update.register, update.value, update.mask)
+        .await?;
+    }
+
+    Ok(())
+  }
+
+  fn into_state<NEXT: State>(self) -> ICM42688<IFACE, D, NEXT> {
+    ICM42688 {
+      iface: self.iface,
+      delay: self.delay,
+      write_verify: self.write_verify,
+      bank: self.bank,
+      state: PhantomData,
+    }
+  }
+
+  /// Read a setting from its register
+  async fn setting<S: Setting>(&mut self) -> Result<S, IFACE::Error> {
+    let value = self.read_reg().await?;
+
+    Ok(S::decode(value)?)
+  }
+
+  /// Change a setting, keeping the other bits of its register
+  async fn set_setting<S: Setting>(&mut self, setting: S) -> Result<(), IFACE::Error> {
+    self.modify_reg(|value| setting.encode(value)).await
+  }
+
+  /// Read register `R`
+  async fn read_reg<R: TypedRegister>(&mut self) -> Result<Value<R>, IFACE::Error> {
+    Ok(Value::new(self.read_register(&R::REGISTER).await?))
+  }
+
+  /// Write register `R`
+  async fn write_reg<R: TypedRegister>(&mut self, value: Value<R>) -> Result<(), IFACE::Error> {
+    self.write_register(&R::REGISTER, value.bits()).await
+  }
+
+  /// Change some fields of register `R`, keeping the other bits
+  async fn modify_reg<R: TypedRegister>(
+    &mut self,
+    f: impl FnOnce(&mut Value<R>),
+  ) -> Result<(), IFACE::Error> {
+    let mut value = self.read_reg().await?;
+    f(&mut value);
+
+    self.write_reg(value).await
+  }
+
+  /// Point `REG_BANK_SEL` at `bank`, unless it is selected already
+  async fn select_bank(&mut self, bank: RegisterBank) -> Result<(), IFACE::Error> {
+    let Some(bytes) = self.bank.select(bank) else {
+      return Ok(());
+    };
+
+    let result = self.iface.write(&bytes).await.map_err(Error::BusError);
+    self.bank.selected(bank, result.is_ok());
+
+    result
+  }
+
+  async fn read_register(&mut self, reg: &dyn Register) -> Result<u8, IFACE::Error> {
+    let mut data = [0u8; 1];
+    self.read_registers(reg, &mut data).await?;
+
+    Ok(data[0])
+  }
+
+  /// Read `buffer.len()` consecutive registers starting at `reg`
+  async fn read_registers(
+    &mut self,
+    reg: &dyn Register,
+    buffer: &mut [u8],
+  ) -> Result<(), IFACE::Error> {
+    check_readable(reg)?;
+
+    self.select_bank(reg.bank()).await?;
+    self
+      .iface
+      .read_registers(reg.addr(), buffer)
+      .await
+      .map_err(Error::BusError)
+  }
+
+  /// Read two registers and combine them into a single value.
+  async fn read_register_u16(
+    &mut self,
+    reg_hi: &dyn Register,
+    reg_lo: &dyn Register,
+  ) -> Result<u16, IFACE::Error> {
+    let data_lo = self.read_register(reg_lo).await?;
+    let data_hi = self.read_register(reg_hi).await?;
+
+    Ok(u16::from_be_bytes([data_hi, data_lo]))
+  }
+
+  /// Write a register, keeping its reserved bits, and verify it if enabled
+  async fn write_register(&mut self, reg: &dyn Register, value: u8) -> Result<(), IFACE::Error> {
+    let value = match reg.reserved() {
+      0x00 => value,
+      reserved => merge_bits(self.read_register(reg).await?, value, !reserved),
+    };
+
+    self.send_register(reg, value).await?;
+
+    let Some(mut check) = WriteCheck::new(reg, value, self.write_verify) else {
+      return Ok(());
+    };
+
+    loop {
+      let actual = self.read_register(reg).await?;
+
+      match check.check(actual) {
+        Verdict::Done => return Ok(()),
+        Verdict::Retry => self.send_register(reg, value).await?,
+        Verdict::Failed(err) => return Err(err.into()),
+      }
+    }
+  }
+
+  /// Write a register without reading it back
+  async fn send_register(&mut self, reg: &dyn Register, value: u8) -> Result<(), IFACE::Error> {
+    check_writable(reg)?;
+
+    self.select_bank(reg.bank()).await?;
+    self
+      .iface
+      .write(&[reg.addr(), value])
+      .await
+      .map_err(Error::BusError)
+  }
+
+  async fn update_register(
+    &mut self,
+    reg: &dyn Register,
+    value: u8,
+    mask: u8,
+  ) -> Result<(), IFACE::Error> {
+    check_writable(reg)?;
+
+    let current = self.read_register(reg).await?;
+    self
+      .write_register(reg, merge_bits(current, value, mask))
+      .await
+  }
+}
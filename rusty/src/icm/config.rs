@@ -1,17 +1,196 @@
 #![allow(unused)]
 
-use super::error::SensorError;
+use super::error::{BusErrorKind, SensorError};
+use super::register::bank0::{
+  ACCEL_CONFIG0, GYRO_ACCEL_CONFIG0, GYRO_CONFIG0, INTF_CONFIG0, PWR_MGMT0,
+};
+use super::register::bank1::INTF_CONFIG4;
+use super::register::{Field, TypedRegister, Value};
+use crate::std::time::Duration;
 
 pub const MAX_I2C_FREQ: u32 = 1_000_000;
 
-pub(crate) trait Bitfield {
-  const BITMASK: u8;
+/// A setting stored in a single register, decoded the same way by the
+/// blocking and the async driver
+pub(crate) trait Setting: Copy {
+  type Register: TypedRegister;
 
-  /// Bit value of a discriminant, shifted to the correct position if
-  /// necessary
+  /// Extract the setting from the value of its register
+  fn decode(value: Value<Self::Register>) -> Result<Self, SensorError>;
+
+  /// Store the setting in the value of its register, keeping the other bits
+  fn encode(self, value: &mut Value<Self::Register>);
+}
+
+/// A setting stored in one field of its register
+pub(crate) trait FieldSetting: TryFrom<u8, Error = SensorError> + Copy {
+  type Register: TypedRegister;
+
+  /// Field of [`Register`](Self::Register) holding the setting
+  const FIELD: Field<Self::Register>;
+
+  /// Field value of a discriminant, not shifted
   fn bits(self) -> u8;
 }
 
+impl<T: FieldSetting> Setting for T {
+  type Register = T::Register;
+
+  fn decode(value: Value<Self::Register>) -> Result<Self, SensorError> {
+    Self::try_from(value.get(Self::FIELD))
+  }
+
+  fn encode(self, value: &mut Value<Self::Register>) {
+    value.set(Self::FIELD, self.bits());
+  }
+}
+
+/// Read back every register write and compare it with the written value
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum WriteVerify {
+  /// Writes are sent without being checked (default)
+  #[default]
+  Disabled,
+  /// Writes are read back and repeated up to `retries` times on a mismatch
+  Enabled { retries: u8 },
+}
+
+/// How often a failed bus transfer is attempted again
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+  /// Attempts made in total, `1` gives up after the first error
+  pub attempts: u8,
+  /// Pause between two attempts
+  pub delay: Duration,
+  /// Errors worth another attempt, every other kind fails immediately
+  pub retryable: &'static [BusErrorKind],
+}
+
+impl RetryPolicy {
+  /// Retry missing acknowledges and timeouts, the errors a noisy line causes
+  ///
+  /// Timeouts have no kind of their own, so every [`BusErrorKind::Other`] is
+  /// retried.
+  pub const fn new(attempts: u8, delay: Duration) -> Self {
+    Self {
+      attempts,
+      delay,
+      retryable: &[BusErrorKind::NoAcknowledge, BusErrorKind::Other],
+    }
+  }
+
+  pub(super) fn retries(&self, kind: BusErrorKind) -> bool {
+    self.retryable.contains(&kind)
+  }
+}
+
+impl Default for RetryPolicy {
+  /// No retries
+  fn default() -> Self {
+    Self::new(1, Duration::ZERO)
+  }
+}
+
+/// Serial interfaces the sensor answers on, `UI_SIFS_CFG` in `INTF_CONFIG0`
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum SerialInterfaces {
+  /// SPI and I²C (default)
+  #[default]
+  Both = 0b00,
+  /// I²C is disabled, for SPI-only boards
+  SpiOnly = 0b11,
+  /// SPI is disabled
+  I2cOnly = 0b10,
+}
+
+impl TryFrom<u8> for SerialInterfaces {
+  type Error = SensorError;
+
+  fn try_from(value: u8) -> Result<Self, Self::Error> {
+    use SerialInterfaces::*;
+
+    match value {
+      0b00 | 0b01 => Ok(Both),
+      0b10 => Ok(I2cOnly),
+      0b11 => Ok(SpiOnly),
+      _ => Err(SensorError::InvalidDiscriminant),
+    }
+  }
+}
+
+impl FieldSetting for SerialInterfaces {
+  type Register = INTF_CONFIG0;
+
+  const FIELD: Field<INTF_CONFIG0> = INTF_CONFIG0::UI_SIFS_CFG;
+
+  fn bits(self) -> u8 {
+    self as u8
+  }
+}
+
+/// Number of SPI wires, `SPI_AP_4WIRE` in `INTF_CONFIG4`
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum SpiWires {
+  /// Data in and out share the `SDIO` pin
+  Three = 0,
+  /// Separate `SDI` and `SDO` pins (default)
+  #[default]
+  Four = 1,
+}
+
+impl TryFrom<u8> for SpiWires {
+  type Error = SensorError;
+
+  fn try_from(value: u8) -> Result<Self, Self::Error> {
+    match value {
+      0 => Ok(SpiWires::Three),
+      1 => Ok(SpiWires::Four),
+      _ => Err(SensorError::InvalidDiscriminant),
+    }
+  }
+}
+
+impl FieldSetting for SpiWires {
+  type Register = INTF_CONFIG4;
+
+  const FIELD: Field<INTF_CONFIG4> = INTF_CONFIG4::SPI_AP_4WIRE;
+
+  fn bits(self) -> u8 {
+    self as u8
+  }
+}
+
+/// How the sensor is connected to the host
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct InterfaceConfig {
+  pub interfaces: SerialInterfaces,
+  pub spi_wires: SpiWires,
+}
+
+impl InterfaceConfig {
+  /// Decode the values of `INTF_CONFIG0` and `INTF_CONFIG4`
+  pub(crate) fn decode(
+    intf_config0: Value<INTF_CONFIG0>,
+    intf_config4: Value<INTF_CONFIG4>,
+  ) -> Result<Self, SensorError> {
+    Ok(Self {
+      interfaces: SerialInterfaces::decode(intf_config0)?,
+      spi_wires: SpiWires::decode(intf_config4)?,
+    })
+  }
+
+  /// Store the configuration in the values of `INTF_CONFIG0` and
+  /// `INTF_CONFIG4`, keeping their other bits
+  pub(crate) fn encode(
+    self,
+    intf_config0: &mut Value<INTF_CONFIG0>,
+    intf_config4: &mut Value<INTF_CONFIG4>,
+  ) {
+    self.interfaces.encode(intf_config0);
+    self.spi_wires.encode(intf_config4);
+  }
+}
+
 /// Defined sloewrate constant for I2C bus
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum I2CSlewRate {
@@ -66,15 +245,6 @@ impl AccelRange {
   }
 }
 
-impl Bitfield for AccelRange {
-  const BITMASK: u8 = 0b0110_0000;
-
-  fn bits(self) -> u8 {
-    // `ACCEL_UI_FS_SEL` occupies bits 6:5 in the register
-    (self as u8) << 5
-  }
-}
-
 impl TryFrom<u8> for AccelRange {
   type Error = SensorError;
 
@@ -91,6 +261,16 @@ impl TryFrom<u8> for AccelRange {
   }
 }
 
+impl FieldSetting for AccelRange {
+  type Register = ACCEL_CONFIG0;
+
+  const FIELD: Field<ACCEL_CONFIG0> = ACCEL_CONFIG0::ACCEL_FS_SEL;
+
+  fn bits(self) -> u8 {
+    self as u8
+  }
+}
+
 /// Configurable ranges of the Gyroscope
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum GyroRange {
@@ -132,15 +312,6 @@ impl GyroRange {
   }
 }
 
-impl Bitfield for GyroRange {
-  const BITMASK: u8 = 0b1110_0000;
-
-  fn bits(self) -> u8 {
-    // `GYRO_UI_FS_SEL` occupies bits 7:5 in the register
-    (self as u8) << 5
-  }
-}
-
 impl TryFrom<u8> for GyroRange {
   type Error = SensorError;
 
@@ -161,66 +332,128 @@ impl TryFrom<u8> for GyroRange {
   }
 }
 
+impl FieldSetting for GyroRange {
+  type Register = GYRO_CONFIG0;
+
+  const FIELD: Field<GYRO_CONFIG0> = GYRO_CONFIG0::GYRO_FS_SEL;
+
+  fn bits(self) -> u8 {
+    self as u8
+  }
+}
+
 /// Configurable power modes of the "IMU PWR_MGMT0"
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum PowerMode {
   /// Gyroscope: OFF, Accelerometer: OFF, Temperature: OFF
   #[default]
-  Sleep = 0b100000,
+  Sleep,
   /// Gyroscope: DRIVE ON Standby mode, Accelerometer: OFF, Temperature: OFF
-  Standby = 0b100100,
+  Standby,
   /// Gyroscope: OFF, Accelerometer: LowPower Mode, Temperature: OFF
-  AccelLowPower = 0b110010,
+  AccelLowPower,
   /// Gyroscope: OFF, Accelerometer: ON, Temperature: OFF
-  AccelLowNoise = 0b100011,
+  AccelLowNoise,
   /// Gyroscope: ON, Accelerometer: OFF, Temperature: OFF
-  GyroLowNoise = 0b101100,
+  GyroLowNoise,
   /// Gyroscope: ON, Accelerometer: ON, Temperature: OFF
-  SixAxisLowNoise = 0b101111,
+  SixAxisLowNoise,
   /// Idle mode RC oscillator is powered ON, Accel and Gyro OFF and Temperature Sensor OFF
-  Idle = 0b110000,
+  Idle,
   /// Gyroscope: OFF, Accelerometer: ON LowPower, Temperature: ON
-  AccelLowPowerTemp = 0b010010,
+  AccelLowPowerTemp,
   /// Gyroscope: OFF, Accelerometer: ON LN, Temperature: ON
-  AccelLowNoiseTemp = 0b010011,
+  AccelLowNoiseTemp,
   /// Gyroscope: ON, Accelerometer: OFF, Temperature: ON
-  GyroLowNoiseTemp = 0b001100,
+  GyroLowNoiseTemp,
   /// Gyroscope: ON, Accelerometer: ON, Temperature: ON
-  SixAxisLowNoiseTemp = 0b001111,
+  SixAxisLowNoiseTemp,
 }
 
-impl Bitfield for PowerMode {
-  const BITMASK: u8 = 0b0011_1111;
+/// `GYRO_MODE` values
+const GYRO_OFF: u8 = 0b00;
+const GYRO_STANDBY: u8 = 0b01;
+const GYRO_LOW_NOISE: u8 = 0b11;
+
+/// `ACCEL_MODE` values, `0b01` turns the accelerometer off as well
+const ACCEL_OFF: u8 = 0b00;
+const ACCEL_LOW_POWER: u8 = 0b10;
+const ACCEL_LOW_NOISE: u8 = 0b11;
+
+impl PowerMode {
+  const ALL: [Self; 11] = [
+    Self::Sleep,
+    Self::Standby,
+    Self::AccelLowPower,
+    Self::AccelLowNoise,
+    Self::GyroLowNoise,
+    Self::SixAxisLowNoise,
+    Self::Idle,
+    Self::AccelLowPowerTemp,
+    Self::AccelLowNoiseTemp,
+    Self::GyroLowNoiseTemp,
+    Self::SixAxisLowNoiseTemp,
+  ];
+
+  /// Values of `TEMP_DIS`, `IDLE`, `GYRO_MODE` and `ACCEL_MODE` in this mode
+  const fn fields(self) -> (u8, u8, u8, u8) {
+    use PowerMode::*;
 
-  fn bits(self) -> u8 {
-    // Temperature sensor on off occupies bit 5  (1 = OFF)
-    // Idle occupies bit 4
-    // `GYRO_MODE` occupies bits 3:2 in the register
-    // `ACCEL_MODE` occupies bits 1:0 in the register
-    self as u8
+    match self {
+      Sleep => (1, 0, GYRO_OFF, ACCEL_OFF),
+      Standby => (1, 0, GYRO_STANDBY, ACCEL_OFF),
+      AccelLowPower => (1, 1, GYRO_OFF, ACCEL_LOW_POWER),
+      AccelLowNoise => (1, 0, GYRO_OFF, ACCEL_LOW_NOISE),
+      GyroLowNoise => (1, 0, GYRO_LOW_NOISE, ACCEL_OFF),
+      SixAxisLowNoise => (1, 0, GYRO_LOW_NOISE, ACCEL_LOW_NOISE),
+      Idle => (1, 1, GYRO_OFF, ACCEL_OFF),
+      AccelLowPowerTemp => (0, 1, GYRO_OFF, ACCEL_LOW_POWER),
+      AccelLowNoiseTemp => (0, 0, GYRO_OFF, ACCEL_LOW_NOISE),
+      GyroLowNoiseTemp => (0, 0, GYRO_LOW_NOISE, ACCEL_OFF),
+      SixAxisLowNoiseTemp => (0, 0, GYRO_LOW_NOISE, ACCEL_LOW_NOISE),
+    }
   }
 }
 
-impl TryFrom<u8> for PowerMode {
-  type Error = SensorError;
-
-  fn try_from(value: u8) -> Result<Self, Self::Error> {
-    use PowerMode::*;
+impl Setting for PowerMode {
+  type Register = PWR_MGMT0;
+
+  /// Find the mode matching the sensor fields
+  ///
+  /// While a sensor measures, `TEMP_DIS` tells the modes apart and `IDLE` is
+  /// ignored. Otherwise it is the other way around, so the reset value decodes
+  /// as [`Sleep`](Self::Sleep).
+  fn decode(value: Value<PWR_MGMT0>) -> Result<Self, SensorError> {
+    let temp_dis = value.get(PWR_MGMT0::TEMP_DIS);
+    let idle = value.get(PWR_MGMT0::IDLE);
+    let gyro = value.get(PWR_MGMT0::GYRO_MODE);
+    let accel = match value.get(PWR_MGMT0::ACCEL_MODE) {
+      0b01 => ACCEL_OFF,
+      mode => mode,
+    };
+    let measuring = gyro == GYRO_LOW_NOISE || accel != ACCEL_OFF;
+
+    Self::ALL
+      .into_iter()
+      .find(|mode| {
+        let (mode_temp_dis, mode_idle, mode_gyro, mode_accel) = mode.fields();
+        let other = if measuring {
+          mode_temp_dis == temp_dis
+        } else {
+          mode_idle == idle
+        };
+        mode_gyro == gyro && mode_accel == accel && other
+      })
+      .ok_or(SensorError::InvalidDiscriminant)
+  }
 
-    match value {
-      0b100000 => Ok(Sleep),
-      0b100100 => Ok(Standby),
-      0b100010 => Ok(AccelLowPower),
-      0b100011 => Ok(AccelLowNoise),
-      0b101100 => Ok(GyroLowNoise),
-      0b101111 => Ok(SixAxisLowNoise),
-      0b110000 => Ok(Idle),
-      0b010010 => Ok(AccelLowPowerTemp),
-      0b010011 => Ok(AccelLowNoiseTemp),
-      0b001100 => Ok(GyroLowNoiseTemp),
-      0b001111 => Ok(Self::SixAxisLowNoiseTemp),
-      _ => Err(SensorError::InvalidDiscriminant),
-    }
+  fn encode(self, value: &mut Value<PWR_MGMT0>) {
+    let (temp_dis, idle, gyro, accel) = self.fields();
+    value
+      .set(PWR_MGMT0::TEMP_DIS, temp_dis)
+      .set(PWR_MGMT0::IDLE, idle)
+      .set(PWR_MGMT0::GYRO_MODE, gyro)
+      .set(PWR_MGMT0::ACCEL_MODE, accel);
   }
 }
 
@@ -284,15 +517,6 @@ impl AccelODR {
   }
 }
 
-impl Bitfield for AccelODR {
-  const BITMASK: u8 = 0b0000_1111;
-
-  fn bits(self) -> u8 {
-    // `ACCEL_ODR` occupies bits 3:0 in the register
-    self as u8
-  }
-}
-
 impl TryFrom<u8> for AccelODR {
   type Error = SensorError;
 
@@ -320,6 +544,16 @@ impl TryFrom<u8> for AccelODR {
   }
 }
 
+impl FieldSetting for AccelODR {
+  type Register = ACCEL_CONFIG0;
+
+  const FIELD: Field<ACCEL_CONFIG0> = ACCEL_CONFIG0::ACCEL_ODR;
+
+  fn bits(self) -> u8 {
+    self as u8
+  }
+}
+
 /// Gyroscope ODR selection values "GYRO_ODR"
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum GyroODR {
@@ -371,15 +605,6 @@ impl GyroODR {
   }
 }
 
-impl Bitfield for GyroODR {
-  const BITMASK: u8 = 0b0000_1111;
-
-  fn bits(self) -> u8 {
-    // `GYRO_ODR` occupies bits 3:0 in the register
-    self as u8
-  }
-}
-
 impl TryFrom<u8> for GyroODR {
   type Error = SensorError;
 
@@ -404,6 +629,16 @@ impl TryFrom<u8> for GyroODR {
   }
 }
 
+impl FieldSetting for GyroODR {
+  type Register = GYRO_CONFIG0;
+
+  const FIELD: Field<GYRO_CONFIG0> = GYRO_CONFIG0::GYRO_ODR;
+
+  fn bits(self) -> u8 {
+    self as u8
+  }
+}
+
 /// Gyroscope LP Filter Bandwidth selection values "GYRO_UI_FILT_BW"
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum GyroBandwidth {
@@ -451,15 +686,6 @@ impl GyroBandwidth {
   }
 }
 
-impl Bitfield for GyroBandwidth {
-  const BITMASK: u8 = 0b0000_1111;
-
-  fn bits(self) -> u8 {
-    // `GYRO_UI_FILT_BW` occupies bits 2:0 in the register
-    self as u8
-  }
-}
-
 impl TryFrom<u8> for GyroBandwidth {
   type Error = SensorError;
 
@@ -482,6 +708,16 @@ impl TryFrom<u8> for GyroBandwidth {
   }
 }
 
+impl FieldSetting for GyroBandwidth {
+  type Register = GYRO_ACCEL_CONFIG0;
+
+  const FIELD: Field<GYRO_ACCEL_CONFIG0> = GYRO_ACCEL_CONFIG0::GYRO_UI_FILT_BW;
+
+  fn bits(self) -> u8 {
+    self as u8
+  }
+}
+
 /// Acceleration Filter Bandwidth selection values
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum AccelBandwidth {
@@ -529,15 +765,6 @@ impl AccelBandwidth {
   }
 }
 
-impl Bitfield for AccelBandwidth {
-  const BITMASK: u8 = 0b0000_1111;
-
-  fn bits(self) -> u8 {
-    // `ACCEL_UI_FILT_BW` occupies bits 2:0 in the register
-    self as u8
-  }
-}
-
 impl TryFrom<u8> for AccelBandwidth {
   type Error = SensorError;
 
@@ -559,3 +786,13 @@ impl TryFrom<u8> for AccelBandwidth {
     }
   }
 }
+
+impl FieldSetting for AccelBandwidth {
+  type Register = GYRO_ACCEL_CONFIG0;
+
+  const FIELD: Field<GYRO_ACCEL_CONFIG0> = GYRO_ACCEL_CONFIG0::ACCEL_UI_FILT_BW;
+
+  fn bits(self) -> u8 {
+    self as u8
+  }
+}
@@ -1,22 +1,20 @@
 // #![allow(unused)]
 
-use crate::{i2c, spi};
+use core::fmt;
 
-#[derive(Debug)]
-pub enum BusError {
-  I2C(i2c::Error),
-  SPI(spi::Error),
-}
+use embedded_hal::{i2c, spi};
 
 /// Any type of error which may occur while interacting with the device
+///
+/// `E` is the error type of the underlying [`Interface`](super::Interface)
 #[derive(Debug)]
-pub enum Error {
-  /// The sensor is not ready
-  NotReady,
+pub enum Error<E> {
   /// Some error originating from the communication bus
-  BusError(BusError),
+  BusError(E),
   /// Some error resulting from interacting with the device
   SensorError(SensorError),
+  /// The interrupt pin could not be awaited
+  PinError,
 }
 
 /// Any type of error specific to this device
@@ -30,17 +28,192 @@ pub enum SensorError {
   BadChip,
   /// Returned if the register bank is set to a invalid value
   ///
-  /// There are 4 banks, 0-3
+  /// There are 5 banks, 0-4
   BankOutOfRange,
   /// Attempted to write to a read-only register
   WriteToReadOnly,
+  /// Attempted to read from a write-only register
+  ReadFromWriteOnly,
+  /// A register did not hold the written value when it was read back
+  ///
+  /// Self-clearing bits are masked out of both `expected` and `actual`.
+  WriteVerifyFailed { reg: u8, expected: u8, actual: u8 },
+  /// A register of a freshly reset sensor does not hold its documented reset
+  /// value
+  ResetValueMismatch {
+    bank: u8,
+    reg: u8,
+    expected: u8,
+    actual: u8,
+  },
   /// Attempted to create an AccelRange or GyroRange enum from an invalid
   /// discriminant
   InvalidDiscriminant,
 }
 
-impl From<SensorError> for Error {
+/// Stored bytes which do not make a [`Snapshot`](super::Snapshot) of this
+/// driver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotError {
+  /// The bytes were stored in another format
+  Format(u8),
+  /// The number of bytes does not match the format
+  Length { expected: usize, actual: usize },
+  /// The snapshot was taken with another register map, its values would end
+  /// up in the wrong registers
+  Layout { expected: u16, actual: u16 },
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::BusError(err) => write!(f, "bus error: {err}"),
+      Error::SensorError(err) => write!(f, "sensor error: {err}"),
+      Error::PinError => write!(f, "interrupt pin error"),
+    }
+  }
+}
+
+impl fmt::Display for SensorError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SensorError::BadChip => write!(f, "unexpected WHO_AM_I value"),
+      SensorError::BankOutOfRange => write!(f, "register bank out of range"),
+      SensorError::WriteToReadOnly => write!(f, "write to a read-only register"),
+      SensorError::ReadFromWriteOnly => write!(f, "read from a write-only register"),
+      SensorError::WriteVerifyFailed {
+        reg,
+        expected,
+        actual,
+      } => write!(
+        f,
+        "register {reg:#04x} reads {actual:#04x} instead of {expected:#04x}"
+      ),
+      SensorError::ResetValueMismatch {
+        bank,
+        reg,
+        expected,
+        actual,
+      } => write!(
+        f,
+        "register {reg:#04x} in bank {bank} resets to {actual:#04x} instead of {expected:#04x}"
+      ),
+      SensorError::InvalidDiscriminant => write!(f, "invalid register value"),
+    }
+  }
+}
+
+impl fmt::Display for SnapshotError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SnapshotError::Format(format) => write!(f, "unknown snapshot format {format}"),
+      SnapshotError::Length { expected, actual } => {
+        write!(f, "snapshot has {actual} bytes instead of {expected}")
+      }
+      SnapshotError::Layout { expected, actual } => write!(
+        f,
+        "snapshot register layout {actual:#06x} does not match {expected:#06x}"
+      ),
+    }
+  }
+}
+
+impl<E> From<SensorError> for Error<E> {
   fn from(err: SensorError) -> Self {
     Error::SensorError(err)
   }
 }
+
+/// A failed state transition of the driver
+///
+/// `driver` is handed back in the state it was in before the transition.
+pub struct TransitionError<D, E> {
+  pub driver: D,
+  pub error: Error<E>,
+}
+
+impl<D, E: fmt::Debug> fmt::Debug for TransitionError<D, E> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("TransitionError")
+      .field("error", &self.error)
+      .finish_non_exhaustive()
+  }
+}
+
+impl<D, E: fmt::Display> fmt::Display for TransitionError<D, E> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.error.fmt(f)
+  }
+}
+
+/// Broad kind of a bus error, decides what is retried and how it is counted
+///
+/// Derived from the embedded-hal error kind of the bus, which has no kind for
+/// timeouts. They count as [`Other`](Self::Other).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusErrorKind {
+  /// The device did not acknowledge its address or data
+  NoAcknowledge,
+  /// Misplaced start/stop condition, lost arbitration or a line held low
+  Bus,
+  Other,
+}
+
+impl From<i2c::ErrorKind> for BusErrorKind {
+  fn from(kind: i2c::ErrorKind) -> Self {
+    match kind {
+      i2c::ErrorKind::NoAcknowledge(_) => BusErrorKind::NoAcknowledge,
+      i2c::ErrorKind::Bus | i2c::ErrorKind::ArbitrationLoss => BusErrorKind::Bus,
+      _ => BusErrorKind::Other,
+    }
+  }
+}
+
+impl From<spi::ErrorKind> for BusErrorKind {
+  fn from(_: spi::ErrorKind) -> Self {
+    BusErrorKind::Other
+  }
+}
+
+/// Bus errors seen by the driver since it was created or the counters were
+/// reset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ErrorCounters {
+  pub no_acknowledge: u32,
+  pub bus: u32,
+  pub other: u32,
+  /// Transfers repeated by the retry policy
+  pub retries: u32,
+  /// Transfers that still failed after all attempts
+  pub failures: u32,
+  /// Bus recoveries that succeeded
+  pub recoveries: u32,
+  /// Bus recoveries that failed themselves
+  pub failed_recoveries: u32,
+}
+
+impl ErrorCounters {
+  /// Number of errors of a kind, counting every failed attempt
+  pub fn get(&self, kind: BusErrorKind) -> u32 {
+    match kind {
+      BusErrorKind::NoAcknowledge => self.no_acknowledge,
+      BusErrorKind::Bus => self.bus,
+      BusErrorKind::Other => self.other,
+    }
+  }
+
+  /// Number of failed attempts of any kind
+  pub fn total(&self) -> u32 {
+    self.no_acknowledge + self.bus + self.other
+  }
+
+  pub(super) fn count(&mut self, kind: BusErrorKind) {
+    let counter = match kind {
+      BusErrorKind::NoAcknowledge => &mut self.no_acknowledge,
+      BusErrorKind::Bus => &mut self.bus,
+      BusErrorKind::Other => &mut self.other,
+    };
+
+    *counter = counter.saturating_add(1);
+  }
+}
@@ -0,0 +1,168 @@
+use core::fmt;
+use embedded_hal::i2c::{self, ErrorType, I2c};
+use embedded_hal::spi::{self, Operation, SpiDevice};
+use embedded_hal_async::{i2c::I2c as AsyncI2c, spi::SpiDevice as AsyncSpiDevice};
+
+use super::asynch;
+use super::error::BusErrorKind;
+
+/// Bus abstraction used by the driver to talk to the sensor
+pub trait Interface {
+  type Error: fmt::Debug;
+
+  /// Broad kind of an error of this bus
+  fn error_kind(err: &Self::Error) -> BusErrorKind;
+
+  /// Write raw bytes to the device, starting with the register address
+  fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+
+  /// Read `buffer.len()` consecutive registers starting at `addr`
+  fn read_registers(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error>;
+
+  /// Try to bring back a bus that keeps failing, does nothing by default
+  fn recover(&mut self) -> Result<(), Self::Error> {
+    Ok(())
+  }
+}
+
+/// Routine freeing a stuck I²C bus, e.g. `|i2c| i2c.recover()` for
+/// [`i2c::I2C`](crate::i2c::I2C)
+pub type RecoverFn<I2C> = fn(&mut I2C) -> Result<(), <I2C as ErrorType>::Error>;
+
+/// I²C interface, talks to the sensor at a fixed slave address
+pub struct I2cInterface<I2C: ErrorType> {
+  i2c: I2C,
+  address: u8,
+  recover: Option<RecoverFn<I2C>>,
+}
+
+impl<I2C: ErrorType> I2cInterface<I2C> {
+  pub fn new(i2c: I2C, address: u8) -> Self {
+    Self {
+      i2c,
+      address,
+      recover: None,
+    }
+  }
+
+  pub(super) fn i2c(&self) -> &I2C {
+    &self.i2c
+  }
+
+  pub(super) fn i2c_mut(&mut self) -> &mut I2C {
+    &mut self.i2c
+  }
+
+  /// Set the routine called by [`Interface::recover`]
+  pub fn set_recover(&mut self, recover: Option<RecoverFn<I2C>>) {
+    self.recover = recover;
+  }
+
+  /// Release the underlying bus
+  pub fn release(self) -> I2C {
+    self.i2c
+  }
+}
+
+impl<I2C: I2c> Interface for I2cInterface<I2C> {
+  type Error = I2C::Error;
+
+  fn error_kind(err: &Self::Error) -> BusErrorKind {
+    i2c::Error::kind(err).into()
+  }
+
+  fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+    self.i2c.write(self.address, bytes)
+  }
+
+  fn read_registers(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+    self.i2c.write_read(self.address, &[addr], buffer)
+  }
+
+  fn recover(&mut self) -> Result<(), Self::Error> {
+    match self.recover {
+      Some(recover) => recover(&mut self.i2c),
+      None => Ok(()),
+    }
+  }
+}
+
+impl<I2C: AsyncI2c> asynch::Interface for I2cInterface<I2C> {
+  type Error = I2C::Error;
+
+  fn error_kind(err: &Self::Error) -> BusErrorKind {
+    i2c::Error::kind(err).into()
+  }
+
+  async fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+    self.i2c.write(self.address, bytes).await
+  }
+
+  async fn read_registers(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+    self.i2c.write_read(self.address, &[addr], buffer).await
+  }
+}
+
+/// SPI interface, chip select is handled by the [`SpiDevice`]
+pub struct SpiInterface<SPI> {
+  spi: SPI,
+}
+
+impl<SPI> SpiInterface<SPI> {
+  /// Set on the address byte to read from a register
+  pub(super) const READ: u8 = 0x80;
+
+  pub fn new(spi: SPI) -> Self {
+    Self { spi }
+  }
+
+  /// Release the underlying bus
+  pub fn release(self) -> SPI {
+    self.spi
+  }
+
+  pub(super) fn spi_mut(&mut self) -> &mut SPI {
+    &mut self.spi
+  }
+}
+
+impl<SPI: SpiDevice> Interface for SpiInterface<SPI> {
+  type Error = SPI::Error;
+
+  fn error_kind(err: &Self::Error) -> BusErrorKind {
+    spi::Error::kind(err).into()
+  }
+
+  fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+    self.spi.write(bytes)
+  }
+
+  fn read_registers(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+    self.spi.transaction(&mut [
+      Operation::Write(&[addr | Self::READ]),
+      Operation::Read(buffer),
+    ])
+  }
+}
+
+impl<SPI: AsyncSpiDevice> asynch::Interface for SpiInterface<SPI> {
+  type Error = SPI::Error;
+
+  fn error_kind(err: &Self::Error) -> BusErrorKind {
+    spi::Error::kind(err).into()
+  }
+
+  async fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+    self.spi.write(bytes).await
+  }
+
+  async fn read_registers(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+    self
+      .spi
+      .transaction(&mut [
+        Operation::Write(&[addr | Self::READ]),
+        Operation::Read(buffer),
+      ])
+      .await
+  }
+}
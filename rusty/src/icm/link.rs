@@ -0,0 +1,178 @@
+use core::fmt;
+
+use super::register::{Bank0, Bank4};
+use super::{Result, SpiInterface, State, DEVICE_ID, ICM42688};
+use crate::spi::{self, SPI};
+
+/// Clock rates tried by [`ICM42688::link_test`], the sensor supports up to
+/// 24 MHz
+pub const LINK_TEST_BAUDRATES: [u32; 8] = [
+  1_000_000, 2_000_000, 4_000_000, 8_000_000, 12_000_000, 16_000_000, 20_000_000, 24_000_000,
+];
+
+/// Registers the driver never changes, compared against their value at the
+/// starting clock rate
+const REFERENCE_REGISTERS: [Bank0; 4] = [
+  Bank0::INTF_CONFIG1,
+  Bank0::GYRO_CONFIG1,
+  Bank0::ACCEL_CONFIG1,
+  Bank0::TMST_CONFIG,
+];
+
+/// Values written to the scratch register, toggling every bit
+const PATTERNS: [u8; 4] = [0x55, 0xAA, 0x00, 0xFF];
+
+/// Settings of [`ICM42688::link_test`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinkTest {
+  /// Rounds of register accesses at every clock rate
+  pub iterations: u16,
+  /// How far below the fastest clean rate the bus is left
+  pub margin_percent: u8,
+}
+
+impl Default for LinkTest {
+  /// 32 rounds, 20 % margin
+  fn default() -> Self {
+    Self {
+      iterations: 32,
+      margin_percent: 20,
+    }
+  }
+}
+
+/// Outcome of the link test at a single clock rate
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LinkRate {
+  pub requested: u32,
+  /// Rate the peripheral actually runs at
+  pub actual: u32,
+  pub transfers: u32,
+  /// Failed transfers and reads returning the wrong value
+  pub errors: u32,
+}
+
+/// Results of [`ICM42688::link_test`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LinkReport {
+  /// One entry per rate of [`LINK_TEST_BAUDRATES`], `None` for rates skipped
+  /// after a slower one failed
+  pub rates: [Option<LinkRate>; LINK_TEST_BAUDRATES.len()],
+  /// Rate the bus was left at
+  pub selected: u32,
+}
+
+impl fmt::Display for LinkReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for rate in self.rates.iter().flatten() {
+      writeln!(
+        f,
+        "{:>8} Hz (actual {:>8} Hz): {} errors in {} transfers",
+        rate.requested, rate.actual, rate.errors, rate.transfers
+      )?;
+    }
+
+    write!(f, "selected {} Hz", self.selected)
+  }
+}
+
+impl<STATE: State> ICM42688<SpiInterface<SPI>, STATE> {
+  /// Find the fastest clock rate the link handles reliably
+  ///
+  /// At every rate of [`LINK_TEST_BAUDRATES`], starting with the slowest,
+  /// `WHO_AM_I` and a few registers the driver never changes are read, and
+  /// the `OFFSET_USER0` scratch register is written and read back. Testing
+  /// stops at the first rate with an error, as a garbled write may already
+  /// have hit another register. The bus is left at the fastest clean rate
+  /// minus the margin, or at the starting rate if none was clean.
+  ///
+  /// Retries are disabled while testing. The error counters and
+  /// `OFFSET_USER0` get their previous values back afterwards.
+  pub fn link_test(&mut self, test: LinkTest) -> Result<LinkReport, spi::Error> {
+    let start = self.iface.spi_mut().get_baudrate();
+
+    let mut reference = [0u8; REFERENCE_REGISTERS.len()];
+    for (value, reg) in reference.iter_mut().zip(&REFERENCE_REGISTERS) {
+      *value = self.read_register(reg)?;
+    }
+
+    let scratch = self.read_register(&Bank4::OFFSET_USER0)?;
+
+    let retry = core::mem::take(&mut self.retry);
+    let counters = self.error_counters;
+    let mut report = LinkReport::default();
+    let mut fastest = None;
+
+    for (slot, &requested) in report.rates.iter_mut().zip(&LINK_TEST_BAUDRATES) {
+      let mut rate = LinkRate {
+        requested,
+        actual: self.set_spi_baudrate(requested),
+        ..Default::default()
+      };
+
+      self.link_test_rate(test.iterations, &reference, &mut rate);
+      *slot = Some(rate);
+
+      if rate.errors > 0 {
+        break;
+      }
+
+      fastest = Some(rate.actual);
+    }
+
+    self.retry = retry;
+    self.error_counters = counters;
+
+    // Clean up at the starting rate, a failed rate may have left another
+    // bank selected than the driver assumes, or garbled cached registers
+    self.set_spi_baudrate(start);
+    self.invalidate_shadow_cache();
+    self.send_register(&Bank4::OFFSET_USER0, scratch)?;
+
+    let margin = 100 - test.margin_percent.min(100) as u32;
+    report.selected = match fastest {
+      Some(rate) => self.set_spi_baudrate(rate / 100 * margin),
+      None => start,
+    };
+
+    Ok(report)
+  }
+
+  /// Run the register accesses of the link test at the current rate
+  fn link_test_rate(&mut self, iterations: u16, reference: &[u8], rate: &mut LinkRate) {
+    let mut tally = |ok: bool| {
+      rate.transfers += 1;
+      rate.errors += !ok as u32;
+    };
+
+    for _ in 0..iterations {
+      let id = self.read_register(&Bank0::WHO_AM_I);
+      tally(id.is_ok_and(|id| id == DEVICE_ID));
+
+      for (reg, expected) in REFERENCE_REGISTERS.iter().zip(reference) {
+        let value = self.read_register(reg);
+        tally(value.is_ok_and(|value| value == *expected));
+      }
+    }
+
+    for i in 0..iterations {
+      let pattern = PATTERNS[i as usize % PATTERNS.len()];
+
+      tally(self.send_register(&Bank4::OFFSET_USER0, pattern).is_ok());
+      let value = self.read_register(&Bank4::OFFSET_USER0);
+      tally(value.is_ok_and(|value| value == pattern));
+    }
+  }
+
+  /// Change the clock rate, also for the device settings applied on select
+  fn set_spi_baudrate(&mut self, baudrate: u32) -> u32 {
+    let spi = self.iface.spi_mut();
+
+    if let Some(mut settings) = spi.device_settings() {
+      settings.max_baudrate = baudrate;
+      spi.set_device_settings(Some(settings));
+    }
+
+    spi.set_baudrate(baudrate)
+  }
+}
@@ -1,361 +1,794 @@
 #![allow(unused)]
 
 use crate::i2c::{self, I2C};
-use crate::spi::{self, SPI};
+use crate::spi::{self, DmaTransfer, SPI};
 use crate::std::{thread, time::Duration};
 pub use accelerometer::{
   error::Error as AccelerometerError,
   vector::{F32x3, I16x3},
   Accelerometer, RawAccelerometer,
 };
+use access::{check_readable, check_writable, merge_bits, BankSelect, Verdict, WriteCheck};
 use config::*;
 use core::f32::consts::PI;
 use core::fmt;
+use core::marker::PhantomData;
 use core::ptr::addr_of_mut;
+use embedded_hal::{i2c::I2c, spi::SpiDevice};
 use error::*;
 use register::*;
+use shadow::Shadow;
 
 pub use config::{
   AccelBandwidth, AccelODR, AccelRange, Address, GyroBandwidth, GyroODR, GyroRange, I2CSlewRate,
-  PowerMode,
+  InterfaceConfig, PowerMode, RetryPolicy, SerialInterfaces, SpiWires, WriteVerify,
 };
-pub use error::Error;
-
+pub use error::{BusErrorKind, Error, ErrorCounters, SensorError, SnapshotError, TransitionError};
+pub use interface::{I2cInterface, Interface, RecoverFn, SpiInterface};
+pub use link::{LinkRate, LinkReport, LinkTest, LINK_TEST_BAUDRATES};
+pub use sample::ImuSample;
+pub use snapshot::{Difference, Snapshot};
+pub use state::{Configured, Ready, State, Streaming, Unconfigured};
+pub use trace::{TraceDirection, TraceEntry, Traced, TRACE_DATA_LEN};
+
+mod access;
+pub mod asynch;
 pub mod config;
 mod error;
+mod interface;
+mod link;
 mod register;
+mod sample;
+mod shadow;
+mod snapshot;
+mod state;
+mod trace;
+
+pub type Result<T, E> = core::result::Result<T, Error<E>>;
 
-pub type Result<T> = core::result::Result<T, Error>;
+/// Outcome of a state transition, `FROM` is handed back on failure
+pub type Transition<FROM, TO, E> = core::result::Result<TO, TransitionError<FROM, E>>;
 
 const GRAVITY: f32 = 9.80665;
 
-pub enum CommunicationProtocol {
-  I2C(i2c::Device),
-  SPI(SPI),
-}
+/// Value of `WHO_AM_I`
+const DEVICE_ID: u8 = 0x47;
 
 /// ICM-42688 driver
-pub struct ICM42688 {
-  comm: CommunicationProtocol,
-  ready: bool,
+///
+/// `STATE` tracks the lifecycle of the sensor: [`init`](Self::init) turns an
+/// [`Unconfigured`] driver into a [`Configured`] one, and
+/// [`start_fifo`](ICM42688::start_fifo) into a [`Streaming`] one. Methods only
+/// exist in the states they are valid in.
+///
+/// # Example
+///
+/// ```
+/// let mut icm = ICM42688::with_spi(spi).init()?;
+/// let sample = icm.sample_raw()?;
+///
+/// let mut icm = icm.start_fifo().map_err(|err| err.error)?;
+/// let len = icm.read_fifo_data(&mut buffer)?;
+/// ```
+pub struct ICM42688<IFACE, STATE = Unconfigured> {
+  iface: IFACE,
+  write_verify: WriteVerify,
+  /// Number of consecutive bus errors after which the bus is recovered
+  recover_after: Option<u8>,
+  bus_errors: u8,
+  retry: RetryPolicy,
+  error_counters: ErrorCounters,
+  bank: BankSelect,
+  /// Copy of the writable registers, if enabled
+  shadow: Option<Shadow>,
+  state: PhantomData<STATE>,
 }
 
-impl Default for ICM42688 {
+impl Default for ICM42688<I2cInterface<I2C>> {
   fn default() -> Self {
-    Self {
-      // i2c: I2C::new(unsafe { addr_of_mut!(crate::i2c0_inst) }),
-      // address: Default::default(),
-      comm: CommunicationProtocol::I2C(i2c::Device::new(
-        I2C::new(unsafe { addr_of_mut!(crate::i2c0_inst) }),
-        Address::default() as _,
-      )),
-      ready: false,
-    }
+    Self::with_i2c(
+      I2C::new(unsafe { addr_of_mut!(crate::i2c0_inst) }),
+      Address::default(),
+    )
+  }
+}
+
+impl<I> ICM42688<I2cInterface<I>>
+where
+  I: I2c,
+{
+  pub fn with_i2c(i2c: I, address: Address) -> Self {
+    Self::new(I2cInterface::new(i2c, address as _))
+  }
+}
+
+impl<I, STATE> ICM42688<I2cInterface<I>, STATE>
+where
+  I: I2c,
+  STATE: State,
+{
+  /// Call `recover` on the bus once `after` transfers in a row have failed
+  ///
+  /// The transfer that triggers the recovery still returns its error, only
+  /// the following ones benefit from a freed bus.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// let mut icm = ICM42688::with_i2c(i2c, Address::default());
+  /// icm.enable_bus_recovery(3, |i2c| i2c.recover());
+  /// ```
+  pub fn enable_bus_recovery(&mut self, after: u8, recover: RecoverFn<I>) {
+    self.iface.set_recover(Some(recover));
+    self.recover_after = Some(after.max(1));
+    self.bus_errors = 0;
+  }
+
+  pub fn disable_bus_recovery(&mut self) {
+    self.iface.set_recover(None);
+    self.recover_after = None;
+  }
+}
+
+impl<I> ICM42688<I2cInterface<I>>
+where
+  I: I2c + i2c::Timeout,
+{
+  /// Like [`with_i2c`](Self::with_i2c), but every register access gives up
+  /// after `timeout` with [`i2c::Error::Timeout`]
+  pub fn with_i2c_timeout(mut i2c: I, address: Address, timeout: Duration) -> Self {
+    i2c.set_timeout(Some(timeout));
+    Self::with_i2c(i2c, address)
   }
 }
 
-impl ICM42688 {
-  pub(crate) const DEVICE_ID: u8 = 0x47;
+impl<I, STATE> ICM42688<I2cInterface<I>, STATE>
+where
+  I: I2c + i2c::Timeout,
+  STATE: State,
+{
+  /// Return the deadline of each register access
+  pub fn timeout(&self) -> Option<Duration> {
+    self.iface.i2c().timeout()
+  }
+
+  /// Set the deadline of each register access, `None` waits forever
+  pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+    self.iface.i2c_mut().set_timeout(timeout);
+  }
+}
+
+impl<S> ICM42688<SpiInterface<S>>
+where
+  S: SpiDevice,
+{
+  pub fn with_spi(spi: S) -> Self {
+    Self::new(SpiInterface::new(spi))
+  }
+}
 
-  pub fn new(protocol: CommunicationProtocol) -> Self {
+impl ICM42688<SpiInterface<SPI>, Streaming> {
+  /// Start draining up to `buffer.len()` bytes from the FIFO with DMA
+  ///
+  /// Only the FIFO level is read blocking, the CPU is free while the data is
+  /// transferred. The driver stays borrowed until the returned transfer is
+  /// dropped. [`read_fifo_data`](ICM42688::read_fifo_data) uses DMA as well,
+  /// but waits for the transfer.
+  ///
+  /// # Safety
+  ///
+  /// The returned transfer must be dropped or waited for, never leaked, see
+  /// [`DmaTransfer`].
+  pub unsafe fn read_fifo_data_dma<'a>(
+    &'a mut self,
+    buffer: &'a mut [u8],
+  ) -> Result<DmaTransfer<'a>, spi::Error> {
+    let len = buffer.len().min(self.read_fifo_cnt()? as usize);
+    let addr = Bank0::FIFO_DATA.addr() | SpiInterface::<SPI>::READ;
+
+    self
+      .iface
+      .spi_mut()
+      .transfer_dma(&[addr], &mut buffer[..len])
+      .map_err(Error::BusError)
+  }
+}
+
+impl<IFACE: Interface> ICM42688<IFACE> {
+  pub fn new(iface: IFACE) -> Self {
     Self {
-      comm: protocol,
-      ..Default::default()
+      iface,
+      write_verify: WriteVerify::default(),
+      recover_after: None,
+      bus_errors: 0,
+      retry: RetryPolicy::default(),
+      error_counters: ErrorCounters::default(),
+      bank: BankSelect::default(),
+      shadow: None,
+      state: PhantomData,
     }
   }
 
-  pub fn with_i2c(i2c: I2C, address: Address) -> Self {
-    Self::new(CommunicationProtocol::I2C(i2c::Device::new(
-      i2c,
-      address as _,
-    )))
+  /// Check the device, reset it and restore the default configuration
+  ///
+  /// Hands back the unconfigured driver on failure.
+  pub fn init(self) -> Transition<Self, ICM42688<IFACE, Configured>, IFACE::Error> {
+    self.transition(|icm| {
+      if dbg!(DEVICE_ID) != dbg!(icm.device_id()?) {
+        return Err(Error::SensorError(SensorError::BadChip));
+      }
+      debug!("Passed device id control");
+
+      icm.reset()?;
+      debug!("Soft reset");
+
+      icm.resync_shadow_cache()?;
+
+      // Make sure that any configuration has been restored to the default values when
+      // initializing the driver.
+      icm.set_setting(AccelRange::default())?;
+      debug!("set_accel_range");
+      icm.set_setting(GyroRange::default())?;
+      debug!("set_gyro_range");
+
+      // The IMU uses `PowerMode::Sleep` by default, which disables both the accel and
+      // gyro, so we enable them both during driver initialization.
+      icm.set_setting(PowerMode::SixAxisLowNoise)?;
+      debug!("set_power_mode");
+
+      Ok(())
+    })
+  }
+
+  /// Write the configuration of `snapshot` to a freshly reset sensor
+  ///
+  /// The accelerometer and gyroscope are turned off first, then the writable
+  /// registers are written bank by bank, and `PWR_MGMT0` goes last to turn the
+  /// sensors back on. Registers trimmed in the factory are left alone, and so
+  /// are the bank 3 registers, which are not documented in the map. The FIFO
+  /// is not restored and stays in bypass mode, whatever state the snapshot was
+  /// taken in: call [`start_fifo`](ICM42688::start_fifo) to stream again. The
+  /// serial interface settings are restored like any other, so the snapshot
+  /// has to come from a sensor connected the same way.
+  ///
+  /// Hands back the unconfigured driver on failure.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// let known_good = icm.snapshot()?;
+  /// // ...
+  /// let icm = icm.soft_reset()?.restore(&known_good)?;
+  /// ```
+  pub fn restore(
+    self,
+    snapshot: &Snapshot,
+  ) -> Transition<Self, ICM42688<IFACE, Configured>, IFACE::Error> {
+    self.transition(|icm| {
+      icm.modify_reg(|power: &mut Value<bank0::PWR_MGMT0>| {
+        power
+          .set(bank0::PWR_MGMT0::GYRO_MODE, 0)
+          .set(bank0::PWR_MGMT0::ACCEL_MODE, 0);
+      })?;
+
+      for reg in registers().filter(|reg| snapshot::restored(*reg)) {
+        icm.write_register(reg, snapshot.get(reg))?;
+      }
+
+      icm.write_register(&Bank0::PWR_MGMT0, snapshot.get(&Bank0::PWR_MGMT0))
+    })
   }
 
-  pub fn with_spi(spi: SPI) -> Self {
-    Self::new(CommunicationProtocol::SPI(spi))
+  /// Check every readable register against its documented reset value
+  ///
+  /// Only meaningful right after power-up or a [`soft_reset`](ICM42688::soft_reset).
+  /// The registers left out of a [`snapshot`](ICM42688::snapshot), which
+  /// change by being read, are skipped. So are the registers trimmed in the
+  /// factory, and the bank 3 registers, whose reset values are not documented
+  /// in the map. Fails on the first mismatch.
+  pub fn verify_reset_values(&mut self) -> Result<(), IFACE::Error> {
+    let checked = registers().filter(|reg| snapshot::captured(*reg));
+
+    for reg in checked {
+      let Some(expected) = reg.reset_value() else {
+        continue;
+      };
+
+      let actual = self.read_register(reg)?;
+      if actual != expected {
+        return Err(Error::SensorError(SensorError::ResetValueMismatch {
+          bank: reg.bank().blk_sel(),
+          reg: reg.addr(),
+          expected,
+          actual,
+        }));
+      }
+    }
+
+    Ok(())
+  }
+}
+
+impl<IFACE: Interface, STATE: State> ICM42688<IFACE, STATE> {
+  /// Return how failed bus transfers are retried
+  pub fn retry_policy(&self) -> RetryPolicy {
+    self.retry
   }
 
-  pub fn init(&mut self) -> Result<()> {
-    self.ready = true;
+  /// Set how failed bus transfers are retried
+  pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+    self.retry = policy;
+  }
+
+  /// Return the bus errors seen so far
+  pub fn error_counters(&self) -> &ErrorCounters {
+    &self.error_counters
+  }
+
+  pub fn reset_error_counters(&mut self) {
+    self.error_counters = ErrorCounters::default();
+  }
 
-    if dbg!(Self::DEVICE_ID) != dbg!(self.device_id()?) {
-      return Err(Error::SensorError(SensorError::BadChip));
+  /// Return how register writes are verified
+  pub fn write_verify(&self) -> WriteVerify {
+    self.write_verify
+  }
+
+  /// Set how register writes are verified
+  pub fn set_write_verify(&mut self, verify: WriteVerify) {
+    self.write_verify = verify;
+  }
+
+  /// Keep a copy of the writable registers in the driver
+  ///
+  /// Settings, scale factors and masked updates are then taken from the copy
+  /// instead of being read from the sensor. The copy is filled by
+  /// [`init`](ICM42688::init) or on first use, and follows every write of the
+  /// driver. Anything changing the registers behind the driver's back, like a
+  /// brown-out, calls for [`resync_shadow_cache`](Self::resync_shadow_cache).
+  pub fn enable_shadow_cache(&mut self) {
+    self.shadow.get_or_insert_with(Shadow::new);
+  }
+
+  pub fn disable_shadow_cache(&mut self) {
+    self.shadow = None;
+  }
+
+  /// Forget the cached registers, they are read again on their next use
+  ///
+  /// The selected bank is forgotten as well, see
+  /// [`invalidate_bank`](Self::invalidate_bank). A soft reset does this on its
+  /// own.
+  pub fn invalidate_shadow_cache(&mut self) {
+    self.invalidate_bank();
+
+    if let Some(shadow) = &mut self.shadow {
+      shadow.invalidate();
     }
-    debug!("Passed device id control");
+  }
 
-    self.soft_reset()?;
-    debug!("Soft reset");
+  /// Forget which register bank is selected, the next access selects it again
+  ///
+  /// The driver only writes `REG_BANK_SEL` when switching banks. Anything
+  /// resetting the sensor behind the driver's back, like a brown-out, leaves
+  /// it pointing at the wrong bank until this is called.
+  pub fn invalidate_bank(&mut self) {
+    self.bank.invalidate();
+  }
 
-    thread::sleep_ms(1);
+  /// Read every cached register from the sensor again
+  ///
+  /// The selected bank is forgotten and selected again first.
+  pub fn resync_shadow_cache(&mut self) -> Result<(), IFACE::Error> {
+    self.invalidate_bank();
 
-    // Make sure that any configuration has been restored to the default values when
-    // initializing the driver.
-    self.set_accel_range(AccelRange::default())?;
-    debug!("set_accel_range");
-    self.set_gyro_range(GyroRange::default())?;
-    debug!("set_gyro_range");
+    if self.shadow.is_none() {
+      return Ok(());
+    }
 
-    // The IMU uses `PowerMode::Sleep` by default, which disables both the accel and
-    // gyro, so we enable them both during driver initialization.
-    self.set_power_mode(PowerMode::SixAxisLowNoise)?;
-    debug!("set_power_mode");
+    for reg in Shadow::registers() {
+      let value = self.read_register(reg)?;
+      self.cache(reg, Some(value));
+    }
 
     Ok(())
   }
 
+  pub fn interface(&self) -> &IFACE {
+    &self.iface
+  }
+
+  pub fn interface_mut(&mut self) -> &mut IFACE {
+    &mut self.iface
+  }
+
+  /// Destroy the driver and return the underlying interface
+  ///
+  /// Bank 0 is selected again on the way out, as other code talking to the
+  /// sensor expects it. This is best effort, a failed write is ignored.
+  pub fn release(mut self) -> IFACE {
+    let _ = self.select_bank(RegisterBank::Bank0);
+
+    self.iface
+  }
+
   /// Read the ID of the connected device
-  pub fn device_id(&self) -> Result<u8> {
+  pub fn device_id(&mut self) -> Result<u8, IFACE::Error> {
     self.read_register(&Bank0::WHO_AM_I)
   }
 
-  /// soft reset the device
-  pub fn soft_reset(&self) -> Result<()> {
-    self.update_register(&Bank0::DEVICE_CONFIG, 0x01, 0b0000_0001)
+  /// Read every register of banks 0 to 4 which is not changed by being read
+  ///
+  /// The interrupt status registers and `FIFO_DATA` are left out, so pending
+  /// interrupts and the FIFO contents survive a snapshot in any state.
+  pub fn snapshot(&mut self) -> Result<Snapshot, IFACE::Error> {
+    let mut snapshot = Snapshot::default();
+
+    for reg in registers().filter(|reg| snapshot::captured(*reg)) {
+      let value = self.read_register(reg)?;
+      self.cache(reg, Some(value));
+      snapshot.set(reg, value);
+    }
+
+    Ok(snapshot)
   }
 
   /// soft reset the device
-  pub fn set_i2c_slew_rate(&self, slew_rate: I2CSlewRate) -> Result<()> {
-    self.write_register(&Bank0::DRIVE_CONFIG, (slew_rate as u8) << 3)?;
-    Ok(())
+  ///
+  /// Every register returns to its reset value, so the driver has to be
+  /// initialized again. Hands back the driver in its current state on failure.
+  pub fn soft_reset(self) -> Transition<Self, ICM42688<IFACE, Unconfigured>, IFACE::Error> {
+    self.transition(Self::reset)
   }
 
+  /// Set the slew rate of the I²C pins, keeping the one of the SPI pins
+  pub fn set_i2c_slew_rate(&mut self, slew_rate: I2CSlewRate) -> Result<(), IFACE::Error> {
+    self.modify_reg(|value: &mut Value<bank0::DRIVE_CONFIG>| {
+      value.set(bank0::DRIVE_CONFIG::I2C_SLEW_RATE, slew_rate as u8);
+    })
+  }
+
+  /// Return how the sensor is connected to the host
+  pub fn interface_config(&mut self) -> Result<InterfaceConfig, IFACE::Error> {
+    let intf_config0 = self.read_reg()?;
+    let intf_config4 = self.read_reg()?;
+
+    Ok(InterfaceConfig::decode(intf_config0, intf_config4)?)
+  }
+
+  /// Configure the serial interfaces of the sensor
+  ///
+  /// The host has to follow right after: switch the SPI to 3-wire mode with
+  /// [`SPI::set_three_wire`] before the next read. Disabling the interface
+  /// the driver talks through makes the sensor unreachable until it is power
+  /// cycled. Neither write is verified, as the sensor may not answer the way
+  /// it was reached anymore.
+  pub fn set_interface_config(&mut self, config: InterfaceConfig) -> Result<(), IFACE::Error> {
+    let mut intf_config4 = self.read_reg()?;
+    let mut intf_config0 = self.read_reg()?;
+    config.encode(&mut intf_config0, &mut intf_config4);
+
+    // Only writes from here on, reads fail until the host switched over too
+    self.send_register(&Bank0::INTF_CONFIG0, intf_config0.bits())?;
+    self.send_register(&Bank1::INTF_CONFIG4, intf_config4.bits())
+  }
+}
+
+impl<IFACE: Interface, STATE: Ready> ICM42688<IFACE, STATE> {
   /// Return the normalized gyro data for each of the three axes
-  pub fn gyro_norm(&self) -> Result<F32x3> {
+  pub fn gyro_norm(&mut self) -> Result<F32x3, IFACE::Error> {
     let range = self.gyro_range()?;
-    let scale = range.scale_factor();
 
     // Scale the raw Gyroscope data using the appropriate factor based on the
     // configured range.
-    let raw = self.gyro_raw()?;
-    let x = raw.x as f32 / scale;
-    let y = raw.y as f32 / scale;
-    let z = raw.z as f32 / scale;
-
-    Ok(F32x3::new(x, y, z))
+    Ok(normalize(self.gyro_raw()?, range.scale_factor()))
   }
 
   /// Read the raw gyro data for each of the three axes
-  pub fn gyro_raw(&self) -> Result<I16x3> {
-    let x = self.read_register_i16(&Bank0::GYRO_DATA_X1, &Bank0::GYRO_DATA_X0)?;
-    let y = self.read_register_i16(&Bank0::GYRO_DATA_Y1, &Bank0::GYRO_DATA_Y0)?;
-    let z = self.read_register_i16(&Bank0::GYRO_DATA_Z1, &Bank0::GYRO_DATA_Z0)?;
+  pub fn gyro_raw(&mut self) -> Result<I16x3, IFACE::Error> {
+    Ok(self.sample_raw()?.gyro)
+  }
 
-    Ok(I16x3::new(x, y, z))
+  /// Read temperature, accelerometer and gyroscope data in a single burst
+  ///
+  /// The sensor auto-increments the register address, so one transaction from
+  /// `TEMP_DATA1` through `GYRO_DATA_Z0` returns values that cannot tear
+  /// between the high and low bytes.
+  pub fn sample_raw(&mut self) -> Result<ImuSample, IFACE::Error> {
+    let mut frame = [0u8; ImuSample::LEN];
+    self.read_registers(&Bank0::TEMP_DATA1, &mut frame)?;
+
+    Ok(ImuSample::from_bytes(&frame))
   }
 
   /// Read the built-in temperature sensor and return the value in degrees
   /// centigrade
-  pub fn temperature(&self) -> Result<f32> {
-    let raw = self.temperature_raw()? as f32;
-    let deg = (raw / 132.48) + 25.0;
-
-    Ok(deg)
+  pub fn temperature(&mut self) -> Result<f32, IFACE::Error> {
+    Ok(celsius(self.temperature_raw()?))
   }
 
   /// Read the raw data from the built-in temperature sensor
-  pub fn temperature_raw(&self) -> Result<i16> {
-    self.read_register_i16(&Bank0::TEMP_DATA1, &Bank0::TEMP_DATA0)
+  pub fn temperature_raw(&mut self) -> Result<i16, IFACE::Error> {
+    Ok(self.sample_raw()?.temperature)
   }
 
   /// Return the currently configured power mode
-  pub fn power_mode(&self) -> Result<PowerMode> {
-    //  `GYRO_MODE` occupies bits 3:2 in the register
-    // `ACCEL_MODE` occupies bits 1:0 in the register
-    let bits = self.read_register(&Bank0::PWR_MGMT0)? & 0x3F;
-
-    Ok(PowerMode::try_from(bits)?)
+  pub fn power_mode(&mut self) -> Result<PowerMode, IFACE::Error> {
+    self.setting()
   }
 
   /// Set the power mode of the IMU
-  pub fn set_power_mode(&self, mode: PowerMode) -> Result<()> {
-    self.update_register(&Bank0::PWR_MGMT0, mode.bits(), PowerMode::BITMASK)
+  pub fn set_power_mode(&mut self, mode: PowerMode) -> Result<(), IFACE::Error> {
+    self.set_setting(mode)
   }
 
   /// Return the currently configured accelerometer range
-  pub fn accel_range(&self) -> Result<AccelRange> {
-    // `ACCEL_UI_FS_SEL` occupies bits 6:5 in the register
-    let fs_sel = self.read_register(&Bank0::ACCEL_CONFIG0)? >> 5;
-
-    Ok(AccelRange::try_from(fs_sel)?)
+  pub fn accel_range(&mut self) -> Result<AccelRange, IFACE::Error> {
+    self.setting()
   }
 
   /// Set the range of the accelerometer
-  pub fn set_accel_range(&self, range: AccelRange) -> Result<()> {
-    self.update_register(&Bank0::ACCEL_CONFIG0, range.bits(), AccelRange::BITMASK)
+  pub fn set_accel_range(&mut self, range: AccelRange) -> Result<(), IFACE::Error> {
+    self.set_setting(range)
   }
 
   /// Return the currently configured gyroscope range
-  pub fn gyro_range(&self) -> Result<GyroRange> {
-    // `GYRO_UI_FS_SEL` occupies bits 6:5 in the register
-    let fs_sel = self.read_register(&Bank0::GYRO_CONFIG0)? >> 5;
-
-    Ok(GyroRange::try_from(fs_sel)?)
+  pub fn gyro_range(&mut self) -> Result<GyroRange, IFACE::Error> {
+    self.setting()
   }
 
   /// Set the range of the gyro
-  pub fn set_gyro_range(&self, range: GyroRange) -> Result<()> {
-    self.update_register(&Bank0::GYRO_CONFIG0, range.bits(), GyroRange::BITMASK)
+  pub fn set_gyro_range(&mut self, range: GyroRange) -> Result<(), IFACE::Error> {
+    self.set_setting(range)
   }
 
   /// Return the currently configured output data rate for the gyroscope
-  pub fn gyro_odr(&self) -> Result<GyroODR> {
-    // `GYRO_ODR` occupies bits 3:0 in the register
-    let odr = self.read_register(&Bank0::GYRO_CONFIG0)? & 0xF;
-
-    Ok(GyroODR::try_from(odr)?)
+  pub fn gyro_odr(&mut self) -> Result<GyroODR, IFACE::Error> {
+    self.setting()
   }
 
   /// Set the output data rate of the gyroscope
-  pub fn set_gyro_odr(&self, odr: GyroODR) -> Result<()> {
-    self.update_register(&Bank0::GYRO_CONFIG0, odr.bits(), GyroODR::BITMASK)
+  pub fn set_gyro_odr(&mut self, odr: GyroODR) -> Result<(), IFACE::Error> {
+    self.set_setting(odr)
   }
 
-  pub fn gyro_bandwith(&self) -> Result<GyroBandwidth> {
-    // `GYRO_UI_FILT_BW` occupies bits 2:0 in the register
-    let bw_sel = self.read_register(&Bank0::GYRO_ACCEL_CONFIG0)? & 0x0F;
-
-    Ok(GyroBandwidth::try_from(bw_sel)?)
+  pub fn gyro_bandwith(&mut self) -> Result<GyroBandwidth, IFACE::Error> {
+    self.setting()
   }
 
   /// Set the gyro_bandwith filter of the gyro
-  pub fn set_gyro_bw(&self, range: GyroBandwidth) -> Result<()> {
-    self.update_register(
-      &Bank0::GYRO_ACCEL_CONFIG0,
-      range.bits(),
-      GyroBandwidth::BITMASK,
-    )
+  pub fn set_gyro_bw(&mut self, range: GyroBandwidth) -> Result<(), IFACE::Error> {
+    self.set_setting(range)
   }
 
   /// Return the currently configured output data rate for the accelerometer
-  pub fn accel_odr(&self) -> Result<AccelODR> {
-    // `ACCEL_ODR` occupies bits 3:0 in the register
-    let odr = self.read_register(&Bank0::ACCEL_CONFIG0)? & 0xF;
-
-    Ok(AccelODR::try_from(odr)?)
+  pub fn accel_odr(&mut self) -> Result<AccelODR, IFACE::Error> {
+    self.setting()
   }
 
   /// Set the output data rate of the accelerometer
-  pub fn set_accel_odr(&self, odr: AccelODR) -> Result<()> {
-    self.update_register(&Bank0::ACCEL_CONFIG0, odr.bits(), AccelODR::BITMASK)
+  pub fn set_accel_odr(&mut self, odr: AccelODR) -> Result<(), IFACE::Error> {
+    self.set_setting(odr)
   }
 
-  pub fn accel_bandwith(&self) -> Result<AccelBandwidth> {
-    // `ACCEL_UI_FILT_BW` occupies bits 2:0 in the register
-    let bw_sel = self.read_register(&Bank0::GYRO_ACCEL_CONFIG0)? >> 4 & 0x0F;
-    let bw = AccelBandwidth::try_from(bw_sel)?;
-
-    Ok(bw)
+  pub fn accel_bandwith(&mut self) -> Result<AccelBandwidth, IFACE::Error> {
+    self.setting()
   }
 
   /// Set the accel_bandwith filter of the accel-meter
-  pub fn set_accel_bw(&self, range: AccelBandwidth) -> Result<()> {
-    self.update_register(
-      &Bank0::GYRO_ACCEL_CONFIG0,
-      range.bits(),
-      AccelBandwidth::BITMASK,
-    )
+  pub fn set_accel_bw(&mut self, range: AccelBandwidth) -> Result<(), IFACE::Error> {
+    self.set_setting(range)
+  }
+
+  /// Route the data ready interrupt to the `INT1` pin, push-pull and active
+  /// high
+  ///
+  /// The pin is latched, it stays high until `INT_STATUS` is read.
+  pub fn set_data_ready_interrupt(&mut self, enabled: bool) -> Result<(), IFACE::Error> {
+    self.update_registers(&data_ready_interrupt(enabled))
+  }
+
+  /// Check whether a new sample is available
+  ///
+  /// Reading `INT_STATUS` clears all of its interrupt flags.
+  pub fn data_ready(&mut self) -> Result<bool, IFACE::Error> {
+    let status = self.read_reg::<bank0::INT_STATUS>()?;
+
+    Ok(status.get(bank0::INT_STATUS::DATA_RDY_INT) != 0)
   }
 
   /// read time stampe from register
-  pub fn read_tmst(&self) -> Result<u16> {
+  pub fn read_tmst(&mut self) -> Result<u16, IFACE::Error> {
     let ped_cnt = self.read_register_u16(&Bank0::TMST_FSYNCH, &Bank0::TMST_FSYNCL)?;
     Ok(ped_cnt)
   }
+}
 
+impl<IFACE: Interface> ICM42688<IFACE, Configured> {
+  /// Start the FIFO in stream mode, recording temperature, accelerometer and
+  /// gyroscope data
+  ///
+  /// Hands back the configured driver on failure.
+  pub fn start_fifo(self) -> Transition<Self, ICM42688<IFACE, Streaming>, IFACE::Error> {
+    self.transition(|icm| icm.update_registers(&FIFO_START))
+  }
+}
+
+impl<IFACE: Interface> ICM42688<IFACE, Streaming> {
   /// read current fifo buffer level, available to read
-  pub fn read_fifo_cnt(&self) -> Result<u16> {
+  pub fn read_fifo_cnt(&mut self) -> Result<u16, IFACE::Error> {
     let fifo_cnt = self.read_register_u16(&Bank0::FIFO_COUNTH, &Bank0::FIFO_COUNTL)?;
     Ok(fifo_cnt)
   }
 
-  pub fn read_fifo(&self, addr: u8) -> Result<u8> {
-    if !self.ready {
-      Err(Error::NotReady)
-    } else {
-      use CommunicationProtocol::*;
-
-      //let mut buffer = [0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8];
-      let buffer: [u8; 8] = match self.comm {
-        I2C(ref device) => device
-          .write_read(&[addr])
-          .map_err(|e| Error::BusError(BusError::I2C(e)))?,
-        SPI(ref device) => {
-          device
-            .write(&[addr], false)
-            .map_err(|e| Error::BusError(BusError::SPI(e)))?;
-          thread::sleep_ms(10);
-          device
-            .read(0, true)
-            .map_err(|e| Error::BusError(BusError::SPI(e)))?
-        }
-      };
+  pub fn read_fifo(&mut self, addr: u8) -> Result<u8, IFACE::Error> {
+    //let mut buffer = [0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8];
+    let mut buffer = [0u8; 8];
+    self.select_bank(RegisterBank::Bank0)?;
+    self.transfer(|iface| iface.read_registers(addr, &mut buffer))?;
 
-      Ok(buffer[0])
-    }
+    Ok(buffer[0])
+  }
+
+  /// Drain up to `buffer.len()` bytes from the FIFO in a single burst
+  ///
+  /// Returns the number of bytes read, which is the smaller of the buffer length
+  /// and the current FIFO level. With the [`SPI`] of this crate, long bursts
+  /// are transferred with DMA.
+  pub fn read_fifo_data(&mut self, buffer: &mut [u8]) -> Result<usize, IFACE::Error> {
+    let len = buffer.len().min(self.read_fifo_cnt()? as usize);
+    self.read_registers(&Bank0::FIFO_DATA, &mut buffer[..len])?;
+
+    Ok(len)
   }
 
-  fn read_bank(&self, bank: RegisterBank, reg: &dyn Register) -> Result<u8> {
-    // See "ACCESSING MREG1, MREG2 AND MREG3 REGISTERS" (page 40)
+  /// Put the FIFO back into bypass mode
+  ///
+  /// Hands back the streaming driver on failure.
+  pub fn stop_fifo(self) -> Transition<Self, ICM42688<IFACE, Configured>, IFACE::Error> {
+    self.transition(|icm| icm.update_registers(&FIFO_STOP))
+  }
+}
 
-    // Wait until the internal clock is running prior to writing.
-    // while self.read_register(&Bank0::MCLK_RDY)? != 0x9 {}
+impl<IFACE: Interface, STATE: State> ICM42688<IFACE, STATE> {
+  /// Write `SOFT_RESET_CONFIG` and wait for the device to come back
+  fn reset(&mut self) -> Result<(), IFACE::Error> {
+    // The device does not respond for 1ms after a soft reset, so the write
+    // cannot be read back.
+    let mut config = self.read_reg::<bank0::DEVICE_CONFIG>()?;
+    config.set(bank0::DEVICE_CONFIG::SOFT_RESET_CONFIG, 1);
+    self.send_register(&Bank0::DEVICE_CONFIG, config.bits())?;
+    thread::sleep_ms(1);
 
-    // Set Bank to read from
-    self.write_register(&Bank0::REG_BANK_SEL, bank.blk_sel())?;
-    // self.write_reg(&Bank0::MADDR_R, reg.addr())?;
-    thread::sleep(Duration::from_millis(10));
+    // `REG_BANK_SEL` and the cached registers are reset as well
+    self.invalidate_shadow_cache();
+    self.bank.reset();
 
-    // Read a value from the register.
-    let result = self.read_register(reg)?;
-    thread::sleep(Duration::from_millis(10));
+    Ok(())
+  }
 
-    // Reset block selection registers.
-    self.update_register(&Bank0::REG_BANK_SEL, 0x00, 0x07)?;
+  /// Apply a list of masked updates
+  fn update_registers(&mut self, updates: &[Update]) -> Result<(), IFACE::Error> {
+    for update in updates {
+      self.update_register(&update.register, update.value, update.mask)?;
+    }
 
-    Ok(result)
+    Ok(())
   }
 
-  fn write_bank(&self, bank: RegisterBank, reg: &dyn Register, value: u8) -> Result<()> {
-    // Set Bank to write to
-    self.update_register(&Bank0::REG_BANK_SEL, bank.blk_sel(), 0x07)?;
+  /// Run `f` and move to state `NEXT` if it succeeds
+  fn transition<NEXT: State>(
+    mut self,
+    f: impl FnOnce(&mut Self) -> Result<(), IFACE::Error>,
+  ) -> Transition<Self, ICM42688<IFACE, NEXT>, IFACE::Error> {
+    match f(&mut self) {
+      Ok(()) => Ok(ICM42688 {
+        iface: self.iface,
+        write_verify: self.write_verify,
+        recover_after: self.recover_after,
+        bus_errors: self.bus_errors,
+        retry: self.retry,
+        error_counters: self.error_counters,
+        bank: self.bank,
+        shadow: self.shadow,
+        state: PhantomData,
+      }),
+      Err(error) => Err(TransitionError {
+        driver: self,
+        error,
+      }),
+    }
+  }
 
-    // Write the value to the register.
-    self.write_register(reg, value)?;
-    thread::sleep(Duration::from_millis(10));
+  /// Read a setting from its register
+  fn setting<T: Setting>(&mut self) -> Result<T, IFACE::Error> {
+    let value = self.read_reg()?;
 
-    // Reset block selection registers.
-    self.update_register(&Bank0::REG_BANK_SEL, 0x00, 0x07)?;
+    Ok(T::decode(value)?)
+  }
 
-    Ok(())
+  /// Change a setting, keeping the other bits of its register
+  fn set_setting<T: Setting>(&mut self, setting: T) -> Result<(), IFACE::Error> {
+    self.modify_reg(|value| setting.encode(value))
   }
 
-  fn set_bank(&self, bank: RegisterBank) -> Result<()> {
-    // Set Bank to write to
-    self.update_register(&Bank0::REG_BANK_SEL, bank.blk_sel(), 0x07)?;
-    Ok(())
+  /// Read register `R`, from the shadow cache if possible
+  fn read_reg<R: TypedRegister>(&mut self) -> Result<Value<R>, IFACE::Error> {
+    Ok(Value::new(self.read_cached(&R::REGISTER)?))
   }
 
-  fn read_register(&self, reg: &dyn Register) -> Result<u8> {
-    if !self.ready {
-      Err(Error::NotReady)
-    } else {
-      use CommunicationProtocol::*;
+  /// Write register `R`
+  fn write_reg<R: TypedRegister>(&mut self, value: Value<R>) -> Result<(), IFACE::Error> {
+    self.write_register(&R::REGISTER, value.bits())
+  }
 
-      let data: [u8; 1] = match self.comm {
-        I2C(ref device) => device
-          .write_read(&[reg.addr()])
-          .map_err(|e| Error::BusError(BusError::I2C(e)))?,
-        SPI(ref device) => device
-          .write_read(&[reg.addr()])
-          .map_err(|e| Error::BusError(BusError::SPI(e)))?,
-      };
+  /// Change some fields of register `R`, keeping the other bits
+  fn modify_reg<R: TypedRegister>(
+    &mut self,
+    f: impl FnOnce(&mut Value<R>),
+  ) -> Result<(), IFACE::Error> {
+    let mut value = self.read_reg()?;
+    f(&mut value);
+
+    self.write_reg(value)
+  }
+
+  /// Point `REG_BANK_SEL` at `bank`, unless it is selected already
+  ///
+  /// `REG_BANK_SEL` is reachable from every bank. After a failed write the
+  /// selected bank is unknown, and the next access selects it again.
+  fn select_bank(&mut self, bank: RegisterBank) -> Result<(), IFACE::Error> {
+    let Some(bytes) = self.bank.select(bank) else {
+      return Ok(());
+    };
+
+    let result = self.transfer(|iface| iface.write(&bytes));
+    self.bank.selected(bank, result.is_ok());
+
+    result
+  }
+
+  /// Read a register from the shadow cache, or from the sensor if it is not
+  /// cached
+  fn read_cached(&mut self, reg: &dyn Register) -> Result<u8, IFACE::Error> {
+    if let Some(value) = self.shadow.as_ref().and_then(|shadow| shadow.get(reg)) {
+      return Ok(value);
+    }
+
+    let value = self.read_register(reg)?;
+    self.cache(reg, Some(value));
+
+    Ok(value)
+  }
 
-      Ok(data[0])
+  /// Update the shadow cache, `None` marks the value of `reg` as unknown
+  fn cache(&mut self, reg: &dyn Register, value: Option<u8>) {
+    if let Some(shadow) = &mut self.shadow {
+      shadow.store(reg, value);
     }
   }
 
+  fn read_register(&mut self, reg: &dyn Register) -> Result<u8, IFACE::Error> {
+    let mut data = [0u8; 1];
+    self.read_registers(reg, &mut data)?;
+
+    Ok(data[0])
+  }
+
+  /// Read `buffer.len()` consecutive registers starting at `reg`
+  fn read_registers(&mut self, reg: &dyn Register, buffer: &mut [u8]) -> Result<(), IFACE::Error> {
+    check_readable(reg)?;
+
+    self.select_bank(reg.bank())?;
+    self.transfer(|iface| iface.read_registers(reg.addr(), buffer))
+  }
+
   /// Read two registers and combine them into a single value.
-  fn read_register_i16(&self, reg_hi: &dyn Register, reg_lo: &dyn Register) -> Result<i16> {
+  fn read_register_i16(
+    &mut self,
+    reg_hi: &dyn Register,
+    reg_lo: &dyn Register,
+  ) -> Result<i16, IFACE::Error> {
     let data_lo = self.read_register(reg_lo)?;
     let data_hi = self.read_register(reg_hi)?;
 
@@ -365,7 +798,11 @@ impl ICM42688 {
   }
 
   /// Read two registers and combine them into a single value.
-  fn read_register_u16(&self, reg_hi: &dyn Register, reg_lo: &dyn Register) -> Result<u16> {
+  fn read_register_u16(
+    &mut self,
+    reg_hi: &dyn Register,
+    reg_lo: &dyn Register,
+  ) -> Result<u16, IFACE::Error> {
     let data_lo = self.read_register(reg_lo)?;
     let data_hi = self.read_register(reg_hi)?;
 
@@ -374,64 +811,197 @@ impl ICM42688 {
     Ok(data)
   }
 
-  fn write_register(&self, reg: &dyn Register, value: u8) -> Result<()> {
-    if reg.read_only() {
-      Err(Error::SensorError(SensorError::WriteToReadOnly))
-    } else if !self.ready {
-      Err(Error::NotReady)
-    } else {
-      use CommunicationProtocol::*;
-
-      match self.comm {
-        I2C(ref device) => device
-          .write(&[reg.addr()], true)
-          .map_err(|e| Error::BusError(BusError::I2C(e))),
-        SPI(ref device) => device
-          .write(&[reg.addr()], true)
-          .map_err(|e| Error::BusError(BusError::SPI(e))),
+  /// Write a register, keeping its reserved bits, and verify it if enabled
+  fn write_register(&mut self, reg: &dyn Register, value: u8) -> Result<(), IFACE::Error> {
+    let value = match reg.reserved() {
+      0x00 => value,
+      reserved => merge_bits(self.read_cached(reg)?, value, !reserved),
+    };
+
+    self.send_register(reg, value)?;
+
+    let Some(mut check) = WriteCheck::new(reg, value, self.write_verify) else {
+      return Ok(());
+    };
+
+    loop {
+      let actual = self.read_register(reg)?;
+
+      match check.check(actual) {
+        Verdict::Done => return Ok(()),
+        Verdict::Retry => self.send_register(reg, value)?,
+        Verdict::Failed(err) => {
+          self.cache(reg, Some(actual));
+          return Err(err.into());
+        }
       }
     }
   }
 
-  fn update_register(&self, reg: &dyn Register, value: u8, mask: u8) -> Result<()> {
-    if reg.read_only() {
-      Err(Error::SensorError(SensorError::WriteToReadOnly))
-    } else {
-      let current = self.read_register(reg)?;
-      let value = (current & !mask) | (value & mask);
+  /// Write a register without reading it back
+  fn send_register(&mut self, reg: &dyn Register, value: u8) -> Result<(), IFACE::Error> {
+    check_writable(reg)?;
+
+    self.select_bank(reg.bank())?;
+    let result = self.transfer(|iface| iface.write(&[reg.addr(), value]));
+    self.cache(reg, result.is_ok().then_some(value));
+
+    result
+  }
+
+  /// Run a bus transfer according to the retry policy
+  ///
+  /// Every failed attempt is counted, and the bus is recovered once too many
+  /// have failed in a row.
+  fn transfer<T>(
+    &mut self,
+    mut f: impl FnMut(&mut IFACE) -> core::result::Result<T, IFACE::Error>,
+  ) -> Result<T, IFACE::Error> {
+    let mut attempt = 1;
+
+    loop {
+      let err = match f(&mut self.iface) {
+        Ok(value) => {
+          self.bus_errors = 0;
+          return Ok(value);
+        }
+        Err(err) => err,
+      };
+
+      let kind = IFACE::error_kind(&err);
+      self.error_counters.count(kind);
+      self.bus_errors = self.bus_errors.saturating_add(1);
+
+      if self
+        .recover_after
+        .is_some_and(|after| self.bus_errors >= after)
+      {
+        self.bus_errors = 0;
+
+        // Report the failed transfer, the outcome of the recovery is only
+        // counted
+        match self.iface.recover() {
+          Ok(()) => {
+            self.error_counters.recoveries = self.error_counters.recoveries.saturating_add(1);
+            debug!("Recovered bus after repeated errors");
+          }
+          Err(err) => {
+            self.error_counters.failed_recoveries =
+              self.error_counters.failed_recoveries.saturating_add(1);
+            debug!("Bus recovery failed: {:?}", IFACE::error_kind(&err));
+          }
+        }
+      }
+
+      if attempt >= self.retry.attempts || !self.retry.retries(kind) {
+        self.error_counters.failures = self.error_counters.failures.saturating_add(1);
+        return Err(Error::BusError(err));
+      }
 
-      self.write_register(reg, value)
+      attempt += 1;
+      self.error_counters.retries = self.error_counters.retries.saturating_add(1);
+      thread::sleep(self.retry.delay);
     }
   }
+
+  fn update_register(
+    &mut self,
+    reg: &dyn Register,
+    value: u8,
+    mask: u8,
+  ) -> Result<(), IFACE::Error> {
+    check_writable(reg)?;
+
+    let current = self.read_cached(reg)?;
+    self.write_register(reg, merge_bits(current, value, mask))
+  }
 }
 
-impl RawAccelerometer<I16x3> for ICM42688 {
-  type Error = Error;
+/// Register updates which flush the FIFO and start it in stream mode
+const FIFO_START: [Update; 3] = {
+  use bank0::{FIFO_CONFIG, FIFO_CONFIG1, SIGNAL_PATH_RESET};
+
+  [
+    SIGNAL_PATH_RESET::FIFO_FLUSH.update(1),
+    FIFO_CONFIG1::FIFO_TEMP_EN
+      .update(1)
+      .and(FIFO_CONFIG1::FIFO_GYRO_EN, 1)
+      .and(FIFO_CONFIG1::FIFO_ACCEL_EN, 1),
+    // Stream-to-FIFO
+    FIFO_CONFIG::FIFO_MODE.update(0b01),
+  ]
+};
 
-  fn accel_raw(&mut self) -> core::result::Result<I16x3, AccelerometerError<Self::Error>> {
-    let x = self.read_register_i16(&Bank0::ACCEL_DATA_X1, &Bank0::ACCEL_DATA_X0)?;
-    let y = self.read_register_i16(&Bank0::ACCEL_DATA_Y1, &Bank0::ACCEL_DATA_Y0)?;
-    let z = self.read_register_i16(&Bank0::ACCEL_DATA_Z1, &Bank0::ACCEL_DATA_Z0)?;
+/// Register updates which put the FIFO into bypass mode
+const FIFO_STOP: [Update; 2] = {
+  use bank0::{FIFO_CONFIG, FIFO_CONFIG1};
+
+  [
+    FIFO_CONFIG::FIFO_MODE.update(0b00),
+    FIFO_CONFIG1::FIFO_TEMP_EN
+      .update(0)
+      .and(FIFO_CONFIG1::FIFO_GYRO_EN, 0)
+      .and(FIFO_CONFIG1::FIFO_ACCEL_EN, 0),
+  ]
+};
+
+/// Register updates which route the data ready interrupt to `INT1`
+fn data_ready_interrupt(enabled: bool) -> [Update; 3] {
+  use bank0::{INT_CONFIG, INT_CONFIG1, INT_SOURCE0};
+
+  [
+    // Latched, push-pull and active high
+    INT_CONFIG::INT1_MODE
+      .update(1)
+      .and(INT_CONFIG::INT1_DRIVE_CIRCUIT, 1)
+      .and(INT_CONFIG::INT1_POLARITY, 1),
+    // Has to be cleared for the interrupt pins to work
+    INT_CONFIG1::INT_ASYNC_RESET.update(0),
+    INT_SOURCE0::UI_DRDY_INT1_EN.update(enabled as u8),
+  ]
+}
+
+/// Scale raw data by the sensitivity of the configured range
+fn normalize(raw: I16x3, scale: f32) -> F32x3 {
+  F32x3::new(
+    raw.x as f32 / scale,
+    raw.y as f32 / scale,
+    raw.z as f32 / scale,
+  )
+}
 
-    Ok(I16x3::new(x, y, z))
+/// Convert a raw temperature reading to degrees centigrade
+fn celsius(raw: i16) -> f32 {
+  (raw as f32 / 132.48) + 25.0
+}
+
+impl<IFACE, STATE> RawAccelerometer<I16x3> for ICM42688<IFACE, STATE>
+where
+  IFACE: Interface,
+  STATE: Ready,
+  IFACE::Error: fmt::Debug,
+{
+  type Error = Error<IFACE::Error>;
+
+  fn accel_raw(&mut self) -> core::result::Result<I16x3, AccelerometerError<Self::Error>> {
+    Ok(self.sample_raw()?.accel)
   }
 }
 
-impl Accelerometer for ICM42688 {
-  type Error = Error;
+impl<IFACE, STATE> Accelerometer for ICM42688<IFACE, STATE>
+where
+  IFACE: Interface,
+  STATE: Ready,
+  IFACE::Error: fmt::Debug,
+{
+  type Error = Error<IFACE::Error>;
 
   fn accel_norm(&mut self) -> core::result::Result<F32x3, AccelerometerError<Self::Error>> {
     let range = self.accel_range()?;
-    let scale = range.scale_factor();
 
     // Scale the raw Accelerometer data using the appropriate factor based on the
     // configured range.
-    let raw = self.accel_raw()?;
-    let x = raw.x as f32 / scale;
-    let y = raw.y as f32 / scale;
-    let z = raw.z as f32 / scale;
-
-    Ok(F32x3::new(x, y, z))
+    Ok(normalize(self.accel_raw()?, range.scale_factor()))
   }
 
   fn sample_rate(&mut self) -> core::result::Result<f32, AccelerometerError<Self::Error>> {
@@ -1,10 +1,12 @@
 #![allow(non_camel_case_types)]
+#![allow(clippy::upper_case_acronyms)]
 #![allow(unused)]
 
-// All reads and writes actually go through User Bank 0, and the remaining banks
-// are accessed via this bank; as such, User Bank 0 has been omitted, given that
-// we are not actually able to "select" it.
-#[derive(Debug, Clone, Copy)]
+use core::marker::PhantomData;
+
+// `REG_BANK_SEL` decides which bank the register addresses refer to, and is
+// reachable from every bank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum RegisterBank {
   Bank0,
   Bank1,
@@ -26,212 +28,705 @@ impl RegisterBank {
   }
 }
 
+/// How the host may access a register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Access {
+  ReadOnly,
+  ReadWrite,
+  WriteOnly,
+}
+
 pub(crate) trait Register {
+  /// Get the bank the register lives in
+  fn bank(&self) -> RegisterBank;
+
   /// Get the address of the register
   fn addr(&self) -> u8;
 
+  /// Name of the register in the data sheet
+  fn name(&self) -> &'static str;
+
+  /// Named bit fields of the register
+  fn fields(&self) -> &'static [NamedField];
+
+  /// How the host may access the register
+  fn access(&self) -> Access;
+
+  /// Value after a reset, `None` for registers trimmed in the factory and
+  /// those without a known reset value
+  fn reset_value(&self) -> Option<u8>;
+
+  /// Bits without a documented function, which have to keep their value
+  ///
+  /// These are the bits outside of the named fields. Registers without named
+  /// fields hold a single value using all of their bits, unless the map marks
+  /// them as undocumented. The drivers keep these bits at their current value
+  /// whenever they write a register.
+  fn reserved(&self) -> u8;
+
   /// Is the register read-only?
-  fn read_only(&self) -> bool;
+  fn read_only(&self) -> bool {
+    self.access() == Access::ReadOnly
+  }
+
+  /// Bits which are cleared by the device itself after being written
+  fn self_clearing(&self) -> u8 {
+    0x00
+  }
+
+  /// Does reading the register change it?
+  ///
+  /// Reading clears the interrupt status flags, and pops the FIFO.
+  fn read_clears(&self) -> bool {
+    false
+  }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub(crate) enum Bank0 {
-  DEVICE_CONFIG = 0x11,
-  DRIVE_CONFIG = 0x13,
-  INT_CONFIG = 0x14,
-  FIFO_CONFIG = 0x16,
-  TEMP_DATA1 = 0x1D,
-  TEMP_DATA0 = 0x1E,
-  ACCEL_DATA_X1 = 0x1F,
-  ACCEL_DATA_X0 = 0x20,
-  ACCEL_DATA_Y1 = 0x21,
-  ACCEL_DATA_Y0 = 0x22,
-  ACCEL_DATA_Z1 = 0x23,
-  ACCEL_DATA_Z0 = 0x24,
-  GYRO_DATA_X1 = 0x25,
-  GYRO_DATA_X0 = 0x26,
-  GYRO_DATA_Y1 = 0x27,
-  GYRO_DATA_Y0 = 0x28,
-  GYRO_DATA_Z1 = 0x29,
-  GYRO_DATA_Z0 = 0x2A,
-  TMST_FSYNCH = 0x2B,
-  TMST_FSYNCL = 0x2C,
-  INT_STATUS = 0x2D,
-  FIFO_COUNTH = 0x2E,
-  FIFO_COUNTL = 0x2F,
-  FIFO_DATA = 0x30,
-  APEX_DATA0 = 0x31,
-  APEX_DATA1 = 0x32,
-  APEX_DATA2 = 0x33,
-  APEX_DATA3 = 0x34,
-  APEX_DATA4 = 0x35,
-  APEX_DATA5 = 0x36,
-  INT_STATUS2 = 0x37,
-  INT_STATUS3 = 0x38,
-  SIGNAL_PATH_RESET = 0x48,
-  INTF_CONFIG0 = 0x4C,
-  INTF_CONFIG1 = 0x4D,
-  PWR_MGMT0 = 0x4E,
-  GYRO_CONFIG0 = 0x4F,
-  ACCEL_CONFIG0 = 0x50,
-  GYRO_CONFIG1 = 0x51,
-  GYRO_ACCEL_CONFIG0 = 0x52,
-  ACCEL_CONFIG1 = 0x53,
-  TMST_CONFIG = 0x54,
-  APEX_CONFIG0 = 0x56,
-  SMD_CONFIG = 0x57,
-  FIFO_CONFIG1 = 0x5F,
-  FIFO_CONFIG2 = 0x60,
-  FIFO_CONFIG3 = 0x61,
-  FSYNC_CONFIG = 0x62,
-  INT_CONFIG0 = 0x63,
-  INT_CONFIG1 = 0x64,
-  INT_SOURCE0 = 0x65,
-  INT_SOURCE1 = 0x66,
-  INT_SOURCE3 = 0x68,
-  INT_SOURCE4 = 0x69,
-  FIFO_LOST_PKT0 = 0x6C,
-  FIFO_LOST_PKT1 = 0x6D,
-  SELF_TEST_CONFIG = 0x70,
-  WHO_AM_I = 0x75,
-  REG_BANK_SEL = 0x76,
-}
-
-impl Register for Bank0 {
-  fn addr(&self) -> u8 {
-    *self as u8
+/// Name and bits of a register field, for code which walks all registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct NamedField {
+  pub name: &'static str,
+  pub mask: u8,
+}
+
+/// A register known at compile time, see the `bank0`..`bank4` modules
+pub(crate) trait TypedRegister: Copy {
+  type Bank: Register + Copy;
+
+  const REGISTER: Self::Bank;
+}
+
+/// Bits `msb` down to `lsb` of register `R`
+///
+/// Fields are only declared by the register map, so a field can never be
+/// applied to the value of another register.
+#[derive(Debug)]
+pub(crate) struct Field<R> {
+  msb: u8,
+  lsb: u8,
+  register: PhantomData<R>,
+}
+
+impl<R> Clone for Field<R> {
+  fn clone(&self) -> Self {
+    *self
   }
+}
 
-  fn read_only(&self) -> bool {
-    use Bank0::*;
-
-    matches!(
-      self,
-      TEMP_DATA1
-        | TEMP_DATA0
-        | ACCEL_DATA_X1
-        | ACCEL_DATA_X0
-        | ACCEL_DATA_Y1
-        | ACCEL_DATA_Y0
-        | ACCEL_DATA_Z1
-        | ACCEL_DATA_Z0
-        | GYRO_DATA_X1
-        | GYRO_DATA_X0
-        | GYRO_DATA_Y1
-        | GYRO_DATA_Y0
-        | GYRO_DATA_Z1
-        | GYRO_DATA_Z0
-        | TMST_FSYNCH
-        | TMST_FSYNCL
-        | INT_STATUS
-        | APEX_DATA0
-        | APEX_DATA1
-        | APEX_DATA2
-        | APEX_DATA3
-        | APEX_DATA4
-        | APEX_DATA5
-        | INT_STATUS2
-        | INT_STATUS3
-        | FIFO_LOST_PKT0
-        | FIFO_LOST_PKT1
-        | FIFO_COUNTH
-        | FIFO_COUNTL
-        | FIFO_DATA
-        | WHO_AM_I
-    )
-  }
-}
-
-#[allow(clippy::upper_case_acronyms)]
-#[derive(Debug, Clone, Copy)]
-pub(crate) enum Bank1 {
-  SENSOR_CONFIG0 = 0x03,
-  GYRO_CONFIG_STATIC2 = 0x0B,
-  GYRO_CONFIG_STATIC3 = 0x0C,
-  GYRO_CONFIG_STATIC4 = 0x0D,
-  GYRO_CONFIG_STATIC5 = 0x0E,
-  GYRO_CONFIG_STATIC6 = 0x0F,
-  GYRO_CONFIG_STATIC7 = 0x10,
-  GYRO_CONFIG_STATIC8 = 0x11,
-  GYRO_CONFIG_STATIC9 = 0x12,
-  GYRO_CONFIG_STATIC10 = 0x13,
-  XG_ST_DATA = 0x5F,
-  YG_ST_DATA = 0x60,
-  ZG_ST_DATA = 0x61,
-  TMSTVAL0 = 0x62,
-  TMSTVAL1 = 0x63,
-  TMSTVAL2 = 0x64,
-  INTF_CONFIG4 = 0x7A,
-  INTF_CONFIG5 = 0x7B,
-  INTF_CONFIG6 = 0x7C,
-}
-
-impl Register for Bank1 {
-  fn addr(&self) -> u8 {
-    *self as u8
+impl<R> Copy for Field<R> {}
+
+impl<R> Field<R> {
+  const fn new(msb: u8, lsb: u8) -> Self {
+    Self {
+      msb,
+      lsb,
+      register: PhantomData,
+    }
   }
 
-  fn read_only(&self) -> bool {
-    matches!(self, Bank1::TMSTVAL0 | Bank1::TMSTVAL1 | Bank1::TMSTVAL2)
+  /// Bits of the register covered by the field
+  pub const fn mask(self) -> u8 {
+    (0xFF >> (7 - self.msb)) & (0xFF << self.lsb)
+  }
+
+  /// Field from the top of `self` to the bottom of `low`, for settings which
+  /// span several fields of the same register
+  pub const fn span(self, low: Self) -> Self {
+    Self::new(self.msb, low.lsb)
+  }
+
+  /// Extract the field from a register value
+  pub const fn get(self, value: u8) -> u8 {
+    (value & self.mask()) >> self.lsb
+  }
+
+  /// Replace the field of a register value with `bits`
+  pub const fn set(self, value: u8, bits: u8) -> u8 {
+    (value & !self.mask()) | ((bits << self.lsb) & self.mask())
   }
 }
 
+impl<R: TypedRegister<Bank = Bank0>> Field<R> {
+  /// Update setting the field of a bank 0 register to `bits`
+  pub const fn update(self, bits: u8) -> Update {
+    Update {
+      register: R::REGISTER,
+      value: self.set(0x00, bits),
+      mask: self.mask(),
+    }
+  }
+}
+
+/// Masked write to a bank 0 register, the other bits keep their value
 #[derive(Debug, Clone, Copy)]
-pub(crate) enum Bank2 {
-  ACCEL_CONFIG_STATIC2 = 0x03,
-  ACCEL_CONFIG_STATIC3 = 0x04,
-  ACCEL_CONFIG_STATIC4 = 0x05,
-  XA_ST_DATA = 0x3B,
-  YA_ST_DATA = 0x3C,
-  ZA_ST_DATA = 0x3D,
+pub(crate) struct Update {
+  pub register: Bank0,
+  pub value: u8,
+  pub mask: u8,
 }
 
-impl Register for Bank2 {
-  fn addr(&self) -> u8 {
-    *self as u8
+impl Update {
+  /// Also set `field` to `bits`, which has to belong to the same register
+  pub const fn and<R: TypedRegister<Bank = Bank0>>(self, field: Field<R>, bits: u8) -> Self {
+    assert!(
+      self.register as u8 == R::REGISTER as u8,
+      "field of another register"
+    );
+
+    Self {
+      register: self.register,
+      value: self.value | field.set(0x00, bits),
+      mask: self.mask | field.mask(),
+    }
   }
+}
 
-  fn read_only(&self) -> bool {
-    false
+/// Value of register `R`, read and written through its fields
+#[derive(Debug)]
+pub(crate) struct Value<R> {
+  bits: u8,
+  register: PhantomData<R>,
+}
+
+impl<R> Clone for Value<R> {
+  fn clone(&self) -> Self {
+    *self
   }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub(crate) enum Bank4 {
-  APEX_CONFIG1 = 0x40,
-  APEX_CONFIG2 = 0x41,
-  APEX_CONFIG3 = 0x42,
-  APEX_CONFIG4 = 0x43,
-  APEX_CONFIG5 = 0x44,
-  APEX_CONFIG6 = 0x45,
-  APEX_CONFIG7 = 0x46,
-  APEX_CONFIG8 = 0x47,
-  APEX_CONFIG9 = 0x48,
-  ACCEL_WOM_X_THR = 0x4A,
-  ACCEL_WOM_Y_THR = 0x4B,
-  ACCEL_WOM_Z_THR = 0x4C,
-  INT_SOURCE6 = 0x4D,
-  INT_SOURCE7 = 0x4E,
-  INT_SOURCE8 = 0x4F,
-  INT_SOURCE9 = 0x50,
-  INT_SOURCE10 = 0x51,
-  OFFSET_USER0 = 0x77,
-  OFFSET_USER1 = 0x78,
-  OFFSET_USER2 = 0x79,
-  OFFSET_USER3 = 0x7A,
-  OFFSET_USER4 = 0x7B,
-  OFFSET_USER5 = 0x7C,
-  OFFSET_USER6 = 0x7D,
-  OFFSET_USER7 = 0x7E,
-  OFFSET_USER8 = 0x7F,
-}
-
-impl Register for Bank4 {
-  fn addr(&self) -> u8 {
-    *self as u8
+impl<R> Copy for Value<R> {}
+
+impl<R: TypedRegister> Value<R> {
+  pub fn new(bits: u8) -> Self {
+    Self {
+      bits,
+      register: PhantomData,
+    }
   }
 
-  fn read_only(&self) -> bool {
+  /// The raw register value
+  pub fn bits(self) -> u8 {
+    self.bits
+  }
+
+  pub fn get(self, field: Field<R>) -> u8 {
+    field.get(self.bits)
+  }
+
+  pub fn set(&mut self, field: Field<R>, bits: u8) -> &mut Self {
+    self.bits = field.set(self.bits, bits);
+    self
+  }
+}
+
+macro_rules! access {
+  (RO) => {
+    Access::ReadOnly
+  };
+  (RW) => {
+    Access::ReadWrite
+  };
+  (WO) => {
+    Access::WriteOnly
+  };
+}
+
+macro_rules! reset_value {
+  (trimmed) => {
+    None
+  };
+  (unknown) => {
+    None
+  };
+  ($value:literal) => {
+    Some($value)
+  };
+}
+
+macro_rules! read_clears {
+  () => {
     false
+  };
+  (clears) => {
+    true
+  };
+}
+
+macro_rules! reserved {
+  ($used:expr) => {
+    if $used == 0x00 {
+      0x00
+    } else {
+      !$used
+    }
+  };
+  ($used:expr, undocumented) => {
+    0xFF
+  };
+}
+
+macro_rules! field {
+  ($msb:literal) => {
+    Field::new($msb, $msb)
+  };
+  ($msb:literal : $lsb:literal) => {
+    Field::new($msb, $lsb)
+  };
+}
+
+/// Declare the registers of the banks
+///
+/// Every register is written as
+/// `NAME = address, access, reset value [, clears self-clearing bits] [, read clears] [, bits undocumented] { fields };`
+/// where the access is `RO`, `RW` or `WO`, and the reset value is `trimmed` for
+/// registers programmed in the factory, or `unknown` if the data sheet does not
+/// give one. `read clears` marks registers changed by being read, see
+/// [`Register::read_clears`]. Fields are given as `NAME[msb:lsb]`, or `NAME[bit]` for single
+/// bits. The bits outside of the fields are reserved, see
+/// [`Register::reserved`], and so is every bit of a register whose bits are
+/// `undocumented`.
+///
+/// This generates the bank enum with its [`Register`] implementation, and a
+/// module with one [`TypedRegister`] per register carrying its fields as
+/// associated constants. [`registers`] and [`index`] cover every bank.
+macro_rules! register_map {
+  ($(
+    $bank:ident in $module:ident {
+      $(
+        $name:ident = $addr:literal, $access:ident, reset $reset:tt $(, clears $clears:literal)?
+          $(, read $read:ident)? $(, bits $bits:ident)? {
+          $($field:ident [$msb:literal $(: $lsb:literal)?]),* $(,)?
+        };
+      )*
+    }
+  )*) => {
+    $(
+      #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+      pub(crate) enum $bank {
+        $($name = $addr,)*
+      }
+
+      impl $bank {
+        /// Every register of the bank, in address order
+        pub const ALL: &'static [$bank] = &[$($bank::$name),*];
+      }
+
+      impl Register for $bank {
+        fn bank(&self) -> RegisterBank {
+          RegisterBank::$bank
+        }
+
+        fn addr(&self) -> u8 {
+          *self as u8
+        }
+
+        fn name(&self) -> &'static str {
+          match self {
+            $($bank::$name => stringify!($name),)*
+          }
+        }
+
+        fn fields(&self) -> &'static [NamedField] {
+          match self {
+            $($bank::$name => {
+              const FIELDS: &[NamedField] = &[$(NamedField {
+                name: stringify!($field),
+                mask: $module::$name::$field.mask(),
+              }),*];
+
+              FIELDS
+            })*
+          }
+        }
+
+        fn access(&self) -> Access {
+          match self {
+            $($bank::$name => access!($access),)*
+          }
+        }
+
+        fn reset_value(&self) -> Option<u8> {
+          match self {
+            $($bank::$name => reset_value!($reset),)*
+          }
+        }
+
+        fn self_clearing(&self) -> u8 {
+          match self {
+            $($bank::$name => 0x00 $(| $clears)?,)*
+          }
+        }
+
+        fn read_clears(&self) -> bool {
+          match self {
+            $($bank::$name => read_clears!($($read)?),)*
+          }
+        }
+
+        fn reserved(&self) -> u8 {
+          match self {
+            $($bank::$name => {
+              const USED: u8 = 0x00 $(| $module::$name::$field.mask())*;
+
+              reserved!(USED $(, $bits)?)
+            })*
+          }
+        }
+      }
+
+      pub(crate) mod $module {
+        use super::*;
+
+        $(
+          #[derive(Debug, Clone, Copy)]
+          pub(crate) struct $name;
+
+          impl TypedRegister for $name {
+            type Bank = $bank;
+
+            const REGISTER: $bank = $bank::$name;
+          }
+
+          impl $name {
+            $(pub(crate) const $field: Field<Self> = field!($msb $(: $lsb)?);)*
+          }
+        )*
+      }
+    )*
+
+    /// Number of registers in all banks
+    pub(crate) const REGISTER_COUNT: usize = 0 $(+ $bank::ALL.len())*;
+
+    /// Fletcher-16 checksum over the bank and address of every register, in the
+    /// order of [`registers`]
+    ///
+    /// Changes whenever a register is added, removed or moved.
+    pub(crate) const REGISTER_LAYOUT: u16 = {
+      const MAP: &[(RegisterBank, u8)] = &[$($((RegisterBank::$bank, $addr),)*)*];
+
+      let mut low = 0u16;
+      let mut high = 0u16;
+      let mut i = 0;
+
+      while i < MAP.len() {
+        let (bank, addr) = MAP[i];
+
+        low = (low + bank as u16) % 255;
+        high = (high + low) % 255;
+        low = (low + addr as u16) % 255;
+        high = (high + low) % 255;
+
+        i += 1;
+      }
+
+      (high << 8) | low
+    };
+
+    /// Every register, bank by bank in address order
+    pub(crate) fn registers() -> impl Iterator<Item = &'static dyn Register> {
+      core::iter::empty()
+        $(.chain($bank::ALL.iter().map(|reg| reg as &dyn Register)))*
+    }
+
+    /// Position of `reg` in [`registers`]
+    pub(crate) fn index(reg: &dyn Register) -> Option<usize> {
+      let mut offset = 0;
+
+      $(
+        if reg.bank() == RegisterBank::$bank {
+          let position = $bank::ALL.binary_search_by_key(&reg.addr(), |reg| reg.addr());
+          return position.ok().map(|position| offset + position);
+        }
+
+        offset += $bank::ALL.len();
+      )*
+
+      None
+    }
+  };
+}
+
+register_map! {
+  Bank0 in bank0 {
+    DEVICE_CONFIG = 0x11, RW, reset 0x00, clears 0b0000_0001 {
+      SPI_MODE[4], SOFT_RESET_CONFIG[0],
+    };
+    DRIVE_CONFIG = 0x13, RW, reset 0x05 {
+      I2C_SLEW_RATE[5:3], SPI_SLEW_RATE[2:0],
+    };
+    INT_CONFIG = 0x14, RW, reset 0x00 {
+      INT2_MODE[5], INT2_DRIVE_CIRCUIT[4], INT2_POLARITY[3],
+      INT1_MODE[2], INT1_DRIVE_CIRCUIT[1], INT1_POLARITY[0],
+    };
+    FIFO_CONFIG = 0x16, RW, reset 0x00 {
+      FIFO_MODE[7:6],
+    };
+    TEMP_DATA1 = 0x1D, RO, reset 0x80 {};
+    TEMP_DATA0 = 0x1E, RO, reset 0x00 {};
+    ACCEL_DATA_X1 = 0x1F, RO, reset 0x80 {};
+    ACCEL_DATA_X0 = 0x20, RO, reset 0x00 {};
+    ACCEL_DATA_Y1 = 0x21, RO, reset 0x80 {};
+    ACCEL_DATA_Y0 = 0x22, RO, reset 0x00 {};
+    ACCEL_DATA_Z1 = 0x23, RO, reset 0x80 {};
+    ACCEL_DATA_Z0 = 0x24, RO, reset 0x00 {};
+    GYRO_DATA_X1 = 0x25, RO, reset 0x80 {};
+    GYRO_DATA_X0 = 0x26, RO, reset 0x00 {};
+    GYRO_DATA_Y1 = 0x27, RO, reset 0x80 {};
+    GYRO_DATA_Y0 = 0x28, RO, reset 0x00 {};
+    GYRO_DATA_Z1 = 0x29, RO, reset 0x80 {};
+    GYRO_DATA_Z0 = 0x2A, RO, reset 0x00 {};
+    TMST_FSYNCH = 0x2B, RO, reset 0x00 {};
+    TMST_FSYNCL = 0x2C, RO, reset 0x00 {};
+    INT_STATUS = 0x2D, RO, reset 0x10, read clears {
+      UI_FSYNC_INT[6], PLL_RDY_INT[5], RESET_DONE_INT[4], DATA_RDY_INT[3],
+      FIFO_THS_INT[2], FIFO_FULL_INT[1], AGC_RDY_INT[0],
+    };
+    FIFO_COUNTH = 0x2E, RO, reset 0x00 {};
+    FIFO_COUNTL = 0x2F, RO, reset 0x00 {};
+    FIFO_DATA = 0x30, RO, reset 0xFF, read clears {};
+    APEX_DATA0 = 0x31, RO, reset 0x00 {};
+    APEX_DATA1 = 0x32, RO, reset 0x00 {};
+    APEX_DATA2 = 0x33, RO, reset 0x00 {};
+    APEX_DATA3 = 0x34, RO, reset 0x04 {
+      DMP_IDLE[2], ACTIVITY_CLASS[1:0],
+    };
+    APEX_DATA4 = 0x35, RO, reset 0x00 {};
+    APEX_DATA5 = 0x36, RO, reset 0x00 {};
+    INT_STATUS2 = 0x37, RO, reset 0x00, read clears {
+      SMD_INT[3], WOM_Z_INT[2], WOM_Y_INT[1], WOM_X_INT[0],
+    };
+    INT_STATUS3 = 0x38, RO, reset 0x00, read clears {
+      STEP_DET_INT[5], STEP_CNT_OFL_INT[4], TILT_DET_INT[3], WAKE_INT[2], SLEEP_INT[1],
+      TAP_DET_INT[0],
+    };
+    SIGNAL_PATH_RESET = 0x4B, RW, reset 0x00, clears 0b0110_1110 {
+      DMP_INIT_EN[6], DMP_MEM_RESET_EN[5], ABORT_AND_RESET[3], TMST_STROBE[2], FIFO_FLUSH[1],
+    };
+    INTF_CONFIG0 = 0x4C, RW, reset 0x30 {
+      FIFO_HOLD_LAST_DATA_EN[7], FIFO_COUNT_REC[6], FIFO_COUNT_ENDIAN[5],
+      SENSOR_DATA_ENDIAN[4], UI_SIFS_CFG[1:0],
+    };
+    INTF_CONFIG1 = 0x4D, RW, reset 0x91 {
+      ACCEL_LP_CLK_SEL[3], RTC_MODE[2], CLKSEL[1:0],
+    };
+    PWR_MGMT0 = 0x4E, RW, reset 0x00 {
+      TEMP_DIS[5], IDLE[4], GYRO_MODE[3:2], ACCEL_MODE[1:0],
+    };
+    GYRO_CONFIG0 = 0x4F, RW, reset 0x06 {
+      GYRO_FS_SEL[7:5], GYRO_ODR[3:0],
+    };
+    ACCEL_CONFIG0 = 0x50, RW, reset 0x06 {
+      ACCEL_FS_SEL[7:5], ACCEL_ODR[3:0],
+    };
+    GYRO_CONFIG1 = 0x51, RW, reset 0x16 {
+      TEMP_FILT_BW[7:5], GYRO_UI_FILT_ORD[3:2], GYRO_DEC2_M2_ORD[1:0],
+    };
+    GYRO_ACCEL_CONFIG0 = 0x52, RW, reset 0x11 {
+      ACCEL_UI_FILT_BW[7:4], GYRO_UI_FILT_BW[3:0],
+    };
+    ACCEL_CONFIG1 = 0x53, RW, reset 0x0D {
+      ACCEL_UI_FILT_ORD[4:3], ACCEL_DEC2_M2_ORD[2:1],
+    };
+    TMST_CONFIG = 0x54, RW, reset 0x23 {
+      TMST_TO_REGS_EN[4], TMST_RES[3], TMST_DELTA_EN[2], TMST_FSYNC_EN[1], TMST_EN[0],
+    };
+    APEX_CONFIG0 = 0x56, RW, reset 0x82 {
+      DMP_POWER_SAVE[7], TAP_ENABLE[6], PED_ENABLE[5], TILT_ENABLE[4], R2W_EN[3],
+      DMP_ODR[1:0],
+    };
+    SMD_CONFIG = 0x57, RW, reset 0x00 {
+      WOM_INT_MODE[3], WOM_MODE[2], SMD_MODE[1:0],
+    };
+    FIFO_CONFIG1 = 0x5F, RW, reset 0x00 {
+      FIFO_RESUME_PARTIAL_RD[6], FIFO_WM_GT_TH[5], FIFO_HIRES_EN[4], FIFO_TMST_FSYNC_EN[3],
+      FIFO_TEMP_EN[2], FIFO_GYRO_EN[1], FIFO_ACCEL_EN[0],
+    };
+    FIFO_CONFIG2 = 0x60, RW, reset 0x00 {
+      FIFO_WM_LO[7:0],
+    };
+    FIFO_CONFIG3 = 0x61, RW, reset 0x00 {
+      FIFO_WM_HI[3:0],
+    };
+    FSYNC_CONFIG = 0x62, RW, reset 0x10 {
+      FSYNC_UI_SEL[6:4], FSYNC_UI_FLAG_CLEAR_SEL[1], FSYNC_POLARITY[0],
+    };
+    INT_CONFIG0 = 0x63, RW, reset 0x00 {
+      UI_DRDY_INT_CLEAR[5:4], FIFO_THS_INT_CLEAR[3:2], FIFO_FULL_INT_CLEAR[1:0],
+    };
+    INT_CONFIG1 = 0x64, RW, reset 0x10 {
+      INT_TPULSE_DURATION[6], INT_TDEASSERT_DISABLE[5], INT_ASYNC_RESET[4],
+    };
+    INT_SOURCE0 = 0x65, RW, reset 0x10 {
+      UI_FSYNC_INT1_EN[6], PLL_RDY_INT1_EN[5], RESET_DONE_INT1_EN[4], UI_DRDY_INT1_EN[3],
+      FIFO_THS_INT1_EN[2], FIFO_FULL_INT1_EN[1], UI_AGC_RDY_INT1_EN[0],
+    };
+    INT_SOURCE1 = 0x66, RW, reset 0x00 {
+      I3C_PROTOCOL_ERROR_INT1_EN[6], SMD_INT1_EN[3], WOM_Z_INT1_EN[2], WOM_Y_INT1_EN[1],
+      WOM_X_INT1_EN[0],
+    };
+    INT_SOURCE3 = 0x68, RW, reset 0x00 {
+      UI_FSYNC_INT2_EN[6], PLL_RDY_INT2_EN[5], RESET_DONE_INT2_EN[4], UI_DRDY_INT2_EN[3],
+      FIFO_THS_INT2_EN[2], FIFO_FULL_INT2_EN[1], UI_AGC_RDY_INT2_EN[0],
+    };
+    INT_SOURCE4 = 0x69, RW, reset 0x00 {
+      I3C_PROTOCOL_ERROR_INT2_EN[6], SMD_INT2_EN[3], WOM_Z_INT2_EN[2], WOM_Y_INT2_EN[1],
+      WOM_X_INT2_EN[0],
+    };
+    FIFO_LOST_PKT0 = 0x6C, RO, reset 0x00 {};
+    FIFO_LOST_PKT1 = 0x6D, RO, reset 0x00 {};
+    SELF_TEST_CONFIG = 0x70, RW, reset 0x00 {
+      ACCEL_ST_POWER[6], EN_AZ_ST[5], EN_AY_ST[4], EN_AX_ST[3], EN_GZ_ST[2], EN_GY_ST[1],
+      EN_GX_ST[0],
+    };
+    WHO_AM_I = 0x75, RO, reset 0x47 {};
+    REG_BANK_SEL = 0x76, RW, reset 0x00 {
+      BANK_SEL[2:0],
+    };
+  }
+
+  Bank1 in bank1 {
+    SENSOR_CONFIG0 = 0x03, RW, reset 0x80 {
+      ZG_DISABLE[5], YG_DISABLE[4], XG_DISABLE[3], ZA_DISABLE[2], YA_DISABLE[1],
+      XA_DISABLE[0],
+    };
+    GYRO_CONFIG_STATIC2 = 0x0B, RW, reset 0xA0 {
+      GYRO_AAF_DIS[1], GYRO_NF_DIS[0],
+    };
+    GYRO_CONFIG_STATIC3 = 0x0C, RW, reset 0x0D {
+      GYRO_AAF_DELT[5:0],
+    };
+    GYRO_CONFIG_STATIC4 = 0x0D, RW, reset 0xAA {
+      GYRO_AAF_DELTSQR_LO[7:0],
+    };
+    GYRO_CONFIG_STATIC5 = 0x0E, RW, reset 0x80 {
+      GYRO_AAF_BITSHIFT[7:4], GYRO_AAF_DELTSQR_HI[3:0],
+    };
+    GYRO_CONFIG_STATIC6 = 0x0F, RW, reset trimmed {};
+    GYRO_CONFIG_STATIC7 = 0x10, RW, reset trimmed {};
+    GYRO_CONFIG_STATIC8 = 0x11, RW, reset trimmed {};
+    GYRO_CONFIG_STATIC9 = 0x12, RW, reset trimmed {};
+    GYRO_CONFIG_STATIC10 = 0x13, RW, reset 0x11 {
+      GYRO_NF_BW_SEL[6:4], GYRO_HPF_BW_IND[3:1], GYRO_HPF_ORD_IND[0],
+    };
+    XG_ST_DATA = 0x5F, RW, reset trimmed {};
+    YG_ST_DATA = 0x60, RW, reset trimmed {};
+    ZG_ST_DATA = 0x61, RW, reset trimmed {};
+    TMSTVAL0 = 0x62, RO, reset 0x00 {};
+    TMSTVAL1 = 0x63, RO, reset 0x00 {};
+    TMSTVAL2 = 0x64, RO, reset 0x00 {};
+    INTF_CONFIG4 = 0x7A, RW, reset 0x83 {
+      I3C_BUS_MODE[6], SPI_AP_4WIRE[1],
+    };
+    INTF_CONFIG5 = 0x7B, RW, reset 0x00 {
+      PIN9_FUNCTION[2:1],
+    };
+    INTF_CONFIG6 = 0x7C, RW, reset 0x5F {
+      ASYNCTIME0_DIS[7], I3C_EN[4], I3C_IBI_BYTE_EN[3], I3C_IBI_EN[2], I3C_DDR_EN[1],
+      I3C_SDR_EN[0],
+    };
+  }
+
+  Bank2 in bank2 {
+    ACCEL_CONFIG_STATIC2 = 0x03, RW, reset 0x30 {
+      ACCEL_AAF_DELT[6:1], ACCEL_AAF_DIS[0],
+    };
+    ACCEL_CONFIG_STATIC3 = 0x04, RW, reset 0x40 {
+      ACCEL_AAF_DELTSQR_LO[7:0],
+    };
+    ACCEL_CONFIG_STATIC4 = 0x05, RW, reset 0x62 {
+      ACCEL_AAF_BITSHIFT[7:4], ACCEL_AAF_DELTSQR_HI[3:0],
+    };
+    XA_ST_DATA = 0x3B, RW, reset trimmed {};
+    YA_ST_DATA = 0x3C, RW, reset trimmed {};
+    ZA_ST_DATA = 0x3D, RW, reset trimmed {};
+  }
+
+  // The bank 3 registers set the pad pull resistors and the clock divider. The
+  // map does not describe their bits or reset values, so they are kept as they
+  // are: every bit is reserved, and the reset check and restore skip them.
+  Bank3 in bank3 {
+    PU_PD_CONFIG1 = 0x06, RW, reset unknown, bits undocumented {};
+    PU_PD_CONFIG2 = 0x0E, RW, reset unknown, bits undocumented {};
+    CLKDIV = 0x2A, RW, reset unknown, bits undocumented {};
+  }
+
+  Bank4 in bank4 {
+    APEX_CONFIG1 = 0x40, RW, reset 0xA2 {
+      LOW_ENERGY_AMP_TH_SEL[7:4], DMP_POWER_SAVE_TIME_SEL[3:0],
+    };
+    APEX_CONFIG2 = 0x41, RW, reset 0x85 {
+      PED_AMP_TH_SEL[7:4], PED_STEP_CNT_TH_SEL[3:0],
+    };
+    APEX_CONFIG3 = 0x42, RW, reset 0x51 {
+      PED_STEP_DET_TH_SEL[7:5], PED_SB_TIMER_TH_SEL[4:2], PED_HI_EN_TH_SEL[1:0],
+    };
+    APEX_CONFIG4 = 0x43, RW, reset 0xA4 {
+      TILT_WAIT_TIME_SEL[7:6], SLEEP_TIME_OUT[5:3],
+    };
+    APEX_CONFIG5 = 0x44, RW, reset 0x8C {
+      MOUNTING_MATRIX[2:0],
+    };
+    APEX_CONFIG6 = 0x45, RW, reset 0x5C {
+      SLEEP_GESTURE_DELAY[2:0],
+    };
+    APEX_CONFIG7 = 0x46, RW, reset 0x45 {
+      TAP_MIN_JERK_THR[7:2], TAP_MAX_PEAK_TOL[1:0],
+    };
+    APEX_CONFIG8 = 0x47, RW, reset 0x5B {
+      TAP_TMAX[6:5], TAP_TAVG[4:3], TAP_TMIN[2:0],
+    };
+    APEX_CONFIG9 = 0x48, RW, reset 0x00 {
+      SENSITIVITY_MODE[0],
+    };
+    ACCEL_WOM_X_THR = 0x4A, RW, reset 0x00 {};
+    ACCEL_WOM_Y_THR = 0x4B, RW, reset 0x00 {};
+    ACCEL_WOM_Z_THR = 0x4C, RW, reset 0x00 {};
+    INT_SOURCE6 = 0x4D, RW, reset 0x00 {
+      STEP_DET_INT1_EN[5], STEP_CNT_OFL_INT1_EN[4], TILT_DET_INT1_EN[3], WAKE_DET_INT1_EN[2],
+      SLEEP_DET_INT1_EN[1], TAP_DET_INT1_EN[0],
+    };
+    INT_SOURCE7 = 0x4E, RW, reset 0x00 {
+      STEP_DET_INT2_EN[5], STEP_CNT_OFL_INT2_EN[4], TILT_DET_INT2_EN[3], WAKE_DET_INT2_EN[2],
+      SLEEP_DET_INT2_EN[1], TAP_DET_INT2_EN[0],
+    };
+    INT_SOURCE8 = 0x4F, RW, reset 0x00 {
+      FSYNC_IBI_EN[5], PLL_RDY_IBI_EN[4], UI_DRDY_IBI_EN[3], FIFO_THS_IBI_EN[2],
+      FIFO_FULL_IBI_EN[1], AGC_RDY_IBI_EN[0],
+    };
+    INT_SOURCE9 = 0x50, RW, reset 0x00 {
+      I3C_PROTOCOL_ERROR_IBI_EN[7], SMD_IBI_EN[4], WOM_Z_IBI_EN[3], WOM_Y_IBI_EN[2],
+      WOM_X_IBI_EN[1],
+    };
+    INT_SOURCE10 = 0x51, RW, reset 0x00 {
+      STEP_DET_IBI_EN[5], STEP_CNT_OFL_IBI_EN[4], TILT_DET_IBI_EN[3], WAKE_DET_IBI_EN[2],
+      SLEEP_DET_IBI_EN[1], TAP_DET_IBI_EN[0],
+    };
+    OFFSET_USER0 = 0x77, RW, reset 0x00 {
+      GYRO_X_OFFUSER_LO[7:0],
+    };
+    OFFSET_USER1 = 0x78, RW, reset 0x00 {
+      GYRO_Y_OFFUSER_HI[7:4], GYRO_X_OFFUSER_HI[3:0],
+    };
+    OFFSET_USER2 = 0x79, RW, reset 0x00 {
+      GYRO_Y_OFFUSER_LO[7:0],
+    };
+    OFFSET_USER3 = 0x7A, RW, reset 0x00 {
+      GYRO_Z_OFFUSER_LO[7:0],
+    };
+    OFFSET_USER4 = 0x7B, RW, reset 0x00 {
+      ACCEL_X_OFFUSER_HI[7:4], GYRO_Z_OFFUSER_HI[3:0],
+    };
+    OFFSET_USER5 = 0x7C, RW, reset 0x00 {
+      ACCEL_X_OFFUSER_LO[7:0],
+    };
+    OFFSET_USER6 = 0x7D, RW, reset 0x00 {
+      ACCEL_Y_OFFUSER_LO[7:0],
+    };
+    OFFSET_USER7 = 0x7E, RW, reset 0x00 {
+      ACCEL_Z_OFFUSER_HI[7:4], ACCEL_Y_OFFUSER_HI[3:0],
+    };
+    OFFSET_USER8 = 0x7F, RW, reset 0x00 {
+      ACCEL_Z_OFFUSER_LO[7:0],
+    };
   }
 }
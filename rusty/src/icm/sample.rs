@@ -0,0 +1,46 @@
+use accelerometer::vector::I16x3;
+
+/// Raw temperature, accelerometer and gyroscope data taken from a single
+/// burst read, so every value belongs to the same sample
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ImuSample {
+  pub temperature: i16,
+  pub accel: I16x3,
+  pub gyro: I16x3,
+}
+
+impl ImuSample {
+  /// Number of bytes from `TEMP_DATA1` through `GYRO_DATA_Z0`
+  pub const LEN: usize = 14;
+
+  /// Decode a frame of big-endian sensor data registers
+  pub fn from_bytes(bytes: &[u8; Self::LEN]) -> Self {
+    let word = |i: usize| i16::from_be_bytes([bytes[i], bytes[i + 1]]);
+
+    Self {
+      temperature: word(0),
+      accel: I16x3::new(word(2), word(4), word(6)),
+      gyro: I16x3::new(word(8), word(10), word(12)),
+    }
+  }
+
+  /// Encode the sample in the layout of the sensor data registers
+  pub fn to_bytes(&self) -> [u8; Self::LEN] {
+    let words = [
+      self.temperature,
+      self.accel.x,
+      self.accel.y,
+      self.accel.z,
+      self.gyro.x,
+      self.gyro.y,
+      self.gyro.z,
+    ];
+    let mut bytes = [0u8; Self::LEN];
+
+    for (chunk, word) in bytes.chunks_exact_mut(2).zip(words) {
+      chunk.copy_from_slice(&word.to_be_bytes());
+    }
+
+    bytes
+  }
+}
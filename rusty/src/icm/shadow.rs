@@ -0,0 +1,57 @@
+use super::register::{index, registers, Access, Bank0, Register, RegisterBank, REGISTER_COUNT};
+
+/// Copy of the writable registers of the sensor
+///
+/// Values are unknown until read from or written to the sensor. Self-clearing
+/// bits are stored cleared, the way the sensor reports them.
+#[derive(Clone, Debug)]
+pub(crate) struct Shadow {
+  values: [Option<u8>; REGISTER_COUNT],
+}
+
+impl Shadow {
+  pub fn new() -> Self {
+    Self {
+      values: [None; REGISTER_COUNT],
+    }
+  }
+
+  /// Registers kept in the cache, grouped by bank
+  pub fn registers() -> impl Iterator<Item = &'static dyn Register> {
+    registers().filter(|reg| cached(*reg))
+  }
+
+  /// Forget every value
+  pub fn invalidate(&mut self) {
+    self.values = [None; REGISTER_COUNT];
+  }
+
+  /// Cached value of `reg`, `None` if it is unknown or not cached at all
+  pub fn get(&self, reg: &dyn Register) -> Option<u8> {
+    self.values[cached_index(reg)?]
+  }
+
+  /// Remember the value of `reg`, or forget it with `None`
+  pub fn store(&mut self, reg: &dyn Register, value: Option<u8>) {
+    if let Some(index) = cached_index(reg) {
+      self.values[index] = value.map(|value| value & !reg.self_clearing());
+    }
+  }
+}
+
+/// Writable registers are cached, except for `REG_BANK_SEL` which the driver
+/// tracks on its own
+fn cached(reg: &dyn Register) -> bool {
+  let bank_sel = reg.bank() == RegisterBank::Bank0 && reg.addr() == Bank0::REG_BANK_SEL.addr();
+
+  reg.access() == Access::ReadWrite && !bank_sel
+}
+
+/// Position of a cached register in [`Shadow::values`]
+fn cached_index(reg: &dyn Register) -> Option<usize> {
+  if cached(reg) {
+    index(reg)
+  } else {
+    None
+  }
+}
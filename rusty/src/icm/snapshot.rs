@@ -0,0 +1,222 @@
+use core::fmt;
+
+use super::error::SnapshotError;
+use super::register::{
+  index, registers, Access, Bank0, Register, RegisterBank, REGISTER_COUNT, REGISTER_LAYOUT,
+};
+
+/// Version of the byte layout of [`Snapshot::to_bytes`]
+const FORMAT: u8 = 1;
+
+/// Format version and register layout in front of the values
+const HEADER: usize = 3;
+
+/// Values of every readable register, taken by
+/// [`ICM42688::snapshot`](super::ICM42688::snapshot)
+///
+/// Registers are stored bank by bank in address order, write-only registers
+/// and those changed by being read, like `INT_STATUS` and `FIFO_DATA`, as
+/// zero. The bytes of [`to_bytes`](Snapshot::to_bytes) can
+/// be stored or sent to another host, they carry a checksum of the register map
+/// so that a driver with another map rejects them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot {
+  values: [u8; Snapshot::LEN],
+}
+
+impl Snapshot {
+  /// Number of registers in a snapshot
+  pub const LEN: usize = REGISTER_COUNT;
+
+  /// Number of bytes of a stored snapshot
+  pub const BYTES: usize = HEADER + Self::LEN;
+
+  /// Load a snapshot stored with [`to_bytes`](Self::to_bytes)
+  ///
+  /// Fails for another format, or a snapshot taken with another register map.
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+    match bytes.first() {
+      Some(&FORMAT) => {}
+      Some(&format) => return Err(SnapshotError::Format(format)),
+      None => {}
+    }
+
+    if bytes.len() != Self::BYTES {
+      return Err(SnapshotError::Length {
+        expected: Self::BYTES,
+        actual: bytes.len(),
+      });
+    }
+
+    let (header, values) = bytes.split_at(HEADER);
+    let layout = u16::from_le_bytes([header[1], header[2]]);
+    if layout != REGISTER_LAYOUT {
+      return Err(SnapshotError::Layout {
+        expected: REGISTER_LAYOUT,
+        actual: layout,
+      });
+    }
+
+    let mut snapshot = Self::default();
+    snapshot.values.copy_from_slice(values);
+
+    Ok(snapshot)
+  }
+
+  /// Format version, register layout and the values of every register
+  pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+    let [low, high] = REGISTER_LAYOUT.to_le_bytes();
+
+    let mut bytes = [0u8; Self::BYTES];
+    bytes[..HEADER].copy_from_slice(&[FORMAT, low, high]);
+    bytes[HEADER..].copy_from_slice(&self.values);
+
+    bytes
+  }
+
+  /// Fields of the configuration registers whose value differs between `self`
+  /// and `other`
+  ///
+  /// Only the read-write registers are compared, the output and status
+  /// registers change with every sample. Reserved bits and registers without
+  /// named fields are compared as well, and reported without a field name.
+  pub fn diff<'a>(&'a self, other: &'a Snapshot) -> impl Iterator<Item = Difference> + 'a {
+    self.differences(other, configuration)
+  }
+
+  /// Like [`diff`](Self::diff), including the output and status registers
+  pub fn diff_all<'a>(&'a self, other: &'a Snapshot) -> impl Iterator<Item = Difference> + 'a {
+    self.differences(other, captured)
+  }
+
+  fn differences<'a>(
+    &'a self,
+    other: &'a Snapshot,
+    compared: fn(&dyn Register) -> bool,
+  ) -> impl Iterator<Item = Difference> + 'a {
+    registers()
+      .filter(move |reg| compared(*reg))
+      .flat_map(move |reg| {
+        let left = self.get(reg);
+        let right = other.get(reg);
+        let rest = match reg.fields() {
+          [] => 0xFF,
+          _ => reg.reserved(),
+        };
+
+        let fields = reg
+          .fields()
+          .iter()
+          .map(|field| (Some(field.name), field.mask));
+        let rest = core::iter::once((None, rest));
+
+        fields
+          .chain(rest)
+          .filter(move |(_, mask)| (left ^ right) & mask != 0)
+          .map(move |(field, mask)| {
+            // Named fields are shifted down, reserved bits are not
+            let shift = if field.is_some() {
+              mask.trailing_zeros()
+            } else {
+              0
+            };
+
+            Difference {
+              bank: reg.bank().blk_sel(),
+              addr: reg.addr(),
+              register: reg.name(),
+              field,
+              left: (left & mask) >> shift,
+              right: (right & mask) >> shift,
+            }
+          })
+      })
+  }
+
+  pub(crate) fn get(&self, reg: &dyn Register) -> u8 {
+    index(reg).map_or(0, |index| self.values[index])
+  }
+
+  pub(crate) fn set(&mut self, reg: &dyn Register, value: u8) {
+    if let Some(index) = index(reg) {
+      self.values[index] = value;
+    }
+  }
+}
+
+impl Default for Snapshot {
+  fn default() -> Self {
+    Self {
+      values: [0; Self::LEN],
+    }
+  }
+}
+
+/// A field with different values in two snapshots, see [`Snapshot::diff`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Difference {
+  pub bank: u8,
+  pub addr: u8,
+  pub register: &'static str,
+  /// `None` for reserved bits, or the whole register if it has no named fields
+  pub field: Option<&'static str>,
+  /// Value in the snapshot `diff` was called on
+  pub left: u8,
+  /// Value in the other snapshot
+  pub right: u8,
+}
+
+impl fmt::Display for Difference {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "bank {} {} (0x{:02X})",
+      self.bank, self.register, self.addr
+    )?;
+
+    if let Some(field) = self.field {
+      write!(f, " {}", field)?;
+    }
+
+    write!(f, ": 0x{:02X} != 0x{:02X}", self.left, self.right)
+  }
+}
+
+/// Registers read into a snapshot, all readable ones except for those changed
+/// by being read: the interrupt status registers would lose pending events, and
+/// `FIFO_DATA` would pop the FIFO
+pub(crate) fn captured(reg: &dyn Register) -> bool {
+  reg.access() != Access::WriteOnly && !reg.read_clears()
+}
+
+/// Registers compared by [`Snapshot::diff`]
+fn configuration(reg: &dyn Register) -> bool {
+  reg.access() == Access::ReadWrite
+}
+
+/// Registers written when restoring a snapshot, in addition to `PWR_MGMT0`
+/// which goes last
+///
+/// Registers trimmed in the factory keep their values, and so do the bank 3
+/// registers, whose bits and reset values the map does not describe. Neither
+/// the bank selection nor the reset and flush bits of `SIGNAL_PATH_RESET` are
+/// of any use to restore. `FIFO_CONFIG` and `FIFO_CONFIG1` are left to
+/// [`start_fifo`](super::ICM42688::start_fifo), so that a restored driver is
+/// never streaming without being in the [`Streaming`](super::Streaming) state.
+pub(crate) fn restored(reg: &dyn Register) -> bool {
+  let skipped = [
+    Bank0::PWR_MGMT0,
+    Bank0::SIGNAL_PATH_RESET,
+    Bank0::FIFO_CONFIG,
+    Bank0::FIFO_CONFIG1,
+    Bank0::REG_BANK_SEL,
+  ];
+
+  reg.access() == Access::ReadWrite
+    && reg.reset_value().is_some()
+    && !skipped.into_iter().any(|skipped| is(reg, skipped))
+}
+
+fn is(reg: &dyn Register, other: Bank0) -> bool {
+  reg.bank() == RegisterBank::Bank0 && reg.addr() == other.addr()
+}
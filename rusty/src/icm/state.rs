@@ -0,0 +1,31 @@
+/// Driver before [`init`](super::ICM42688::init), only the device ID and the
+/// bus settings are available
+pub struct Unconfigured;
+
+/// Initialized driver, configuration and sensor data are available
+pub struct Configured;
+
+/// Configured driver with the FIFO running in stream mode, which adds the
+/// FIFO reads
+pub struct Streaming;
+
+/// Lifecycle state of the driver
+pub trait State: sealed::Sealed {}
+
+/// States in which the sensor is configured and produces data
+pub trait Ready: State {}
+
+impl State for Unconfigured {}
+impl State for Configured {}
+impl State for Streaming {}
+
+impl Ready for Configured {}
+impl Ready for Streaming {}
+
+mod sealed {
+  pub trait Sealed {}
+
+  impl Sealed for super::Unconfigured {}
+  impl Sealed for super::Configured {}
+  impl Sealed for super::Streaming {}
+}
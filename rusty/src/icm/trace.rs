@@ -0,0 +1,211 @@
+use core::fmt;
+
+use super::asynch;
+use super::error::BusErrorKind;
+use super::interface::Interface;
+use crate::std::time::Instant;
+
+/// Number of data bytes kept per entry, longer transfers are cut off
+pub const TRACE_DATA_LEN: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceDirection {
+  Read,
+  Write,
+}
+
+/// A single transaction recorded by [`Traced`]
+#[derive(Debug, Clone, Copy)]
+pub struct TraceEntry {
+  pub timestamp: Instant,
+  pub direction: TraceDirection,
+  pub register: u8,
+  /// Number of data bytes transferred, the register address excluded
+  pub len: usize,
+  data: [u8; TRACE_DATA_LEN],
+  pub result: Result<(), BusErrorKind>,
+}
+
+impl TraceEntry {
+  /// The first [`TRACE_DATA_LEN`] data bytes
+  pub fn data(&self) -> &[u8] {
+    &self.data[..self.len.min(TRACE_DATA_LEN)]
+  }
+}
+
+impl fmt::Display for TraceEntry {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let direction = match self.direction {
+      TraceDirection::Read => "R",
+      TraceDirection::Write => "W",
+    };
+
+    write!(
+      f,
+      "{:>12?} {direction} {:#04x} [{}]",
+      self.timestamp, self.register, self.len
+    )?;
+
+    for byte in self.data() {
+      write!(f, " {byte:02x}")?;
+    }
+
+    if self.len > TRACE_DATA_LEN {
+      write!(f, " ..")?;
+    }
+
+    match self.result {
+      Ok(()) => Ok(()),
+      Err(kind) => write!(f, " failed: {kind:?}"),
+    }
+  }
+}
+
+/// Interface wrapper recording every transaction in a ring buffer of `N`
+/// entries, the oldest ones are overwritten
+///
+/// # Example
+///
+/// ```
+/// let icm = ICM42688::new(Traced::<_, 64>::new(SpiInterface::new(spi))).init()?;
+/// icm.interface().dump();
+/// ```
+pub struct Traced<IFACE, const N: usize = 32> {
+  iface: IFACE,
+  entries: [Option<TraceEntry>; N],
+  /// Index the next entry is written to
+  next: usize,
+  enabled: bool,
+}
+
+#[allow(unused)]
+impl<IFACE, const N: usize> Traced<IFACE, N> {
+  pub fn new(iface: IFACE) -> Self {
+    Self {
+      iface,
+      entries: [None; N],
+      next: 0,
+      enabled: true,
+    }
+  }
+
+  /// Destroy the wrapper and return the traced interface
+  pub fn release(self) -> IFACE {
+    self.iface
+  }
+
+  /// Pause or resume recording
+  pub fn set_enabled(&mut self, enabled: bool) {
+    self.enabled = enabled;
+  }
+
+  pub fn clear(&mut self) {
+    self.entries = [None; N];
+    self.next = 0;
+  }
+
+  /// Recorded entries, oldest first
+  pub fn entries(&self) -> impl Iterator<Item = &TraceEntry> {
+    let (newer, older) = self.entries.split_at(self.next);
+    older.iter().chain(newer).flatten()
+  }
+
+  /// Print every recorded entry
+  pub fn dump(&self) {
+    println!("{self}");
+  }
+
+  fn record(
+    &mut self,
+    direction: TraceDirection,
+    register: u8,
+    bytes: &[u8],
+    result: Result<(), BusErrorKind>,
+  ) {
+    if !self.enabled || N == 0 {
+      return;
+    }
+
+    let mut data = [0u8; TRACE_DATA_LEN];
+    let len = bytes.len().min(TRACE_DATA_LEN);
+    data[..len].copy_from_slice(&bytes[..len]);
+
+    self.entries[self.next] = Some(TraceEntry {
+      timestamp: Instant::now(),
+      direction,
+      register,
+      len: bytes.len(),
+      data,
+      result,
+    });
+    self.next = (self.next + 1) % N;
+  }
+}
+
+impl<IFACE, const N: usize> fmt::Display for Traced<IFACE, N> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for entry in self.entries() {
+      writeln!(f, "{entry}")?;
+    }
+
+    Ok(())
+  }
+}
+
+impl<IFACE: Interface, const N: usize> Interface for Traced<IFACE, N> {
+  type Error = IFACE::Error;
+
+  fn error_kind(err: &Self::Error) -> BusErrorKind {
+    IFACE::error_kind(err)
+  }
+
+  fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+    let result = self.iface.write(bytes);
+
+    if let Some((&register, data)) = bytes.split_first() {
+      let kind = result.as_ref().map(|_| ()).map_err(IFACE::error_kind);
+      self.record(TraceDirection::Write, register, data, kind);
+    }
+
+    result
+  }
+
+  fn read_registers(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+    let result = self.iface.read_registers(addr, buffer);
+    let kind = result.as_ref().map(|_| ()).map_err(IFACE::error_kind);
+    self.record(TraceDirection::Read, addr, buffer, kind);
+
+    result
+  }
+
+  fn recover(&mut self) -> Result<(), Self::Error> {
+    self.iface.recover()
+  }
+}
+
+impl<IFACE: asynch::Interface, const N: usize> asynch::Interface for Traced<IFACE, N> {
+  type Error = IFACE::Error;
+
+  fn error_kind(err: &Self::Error) -> BusErrorKind {
+    IFACE::error_kind(err)
+  }
+
+  async fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+    let result = self.iface.write(bytes).await;
+
+    if let Some((&register, data)) = bytes.split_first() {
+      let kind = result.as_ref().map(|_| ()).map_err(IFACE::error_kind);
+      self.record(TraceDirection::Write, register, data, kind);
+    }
+
+    result
+  }
+
+  async fn read_registers(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+    let result = self.iface.read_registers(addr, buffer).await;
+    let kind = result.as_ref().map(|_| ()).map_err(IFACE::error_kind);
+    self.record(TraceDirection::Read, addr, buffer, kind);
+
+    result
+  }
+}
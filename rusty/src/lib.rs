@@ -9,12 +9,13 @@ extern crate alloc;
 // use core::ptr::addr_of_mut;
 // use i2c::I2C;
 use gpio::Pin;
-use icm::{Accelerometer, ICM42688};
+use icm::{Accelerometer, LinkTest, ICM42688};
 use spi::SPI;
 use std::{ffi, thread};
 
 #[macro_use]
 mod std;
+mod dma;
 mod error;
 mod gpio;
 mod i2c;
@@ -43,9 +44,14 @@ fn main() {
   // let mut spi = SPI::with_chip_select(Default::default(), gpio::Pin::new_init(PICO_DEFAULT_SPI_CSN_PIN));
   spi.init(1_000_000);
 
-  // let mut icm = ICM42688::with_i2c(i2c, icm::Address::Primary);
-  let mut icm = ICM42688::with_spi(spi);
-  icm.init().unwrap();
+  // let mut icm = ICM42688::with_i2c(i2c, icm::Address::Primary).init().unwrap();
+  let mut icm = ICM42688::with_spi(spi).init().unwrap();
+
+  // Start slow, then settle on the fastest clock the wiring handles
+  match icm.link_test(LinkTest::default()) {
+    Ok(report) => println!("SPI link test:\n{report}"),
+    Err(err) => println!("SPI link test failed: {err}"),
+  }
 
   thread::sleep_ms(5000);
 
@@ -57,6 +63,7 @@ fn main() {
       .inspect(|temperature| {
         dbg!(temperature);
       })
+      .inspect_err(|err| println!("Reading temperature failed: {err}"))
       .ok();
 
     icm
@@ -64,6 +71,11 @@ fn main() {
       .inspect(|accel_data| {
         dbg!(accel_data);
       })
+      // Report the driver error wrapped by the `accelerometer` error
+      .inspect_err(|err| match err.cause() {
+        Some(err) => println!("Reading accel failed: {err}"),
+        None => println!("Reading accel failed: {:?}", err.kind()),
+      })
       .ok();
 
     icm
@@ -71,10 +83,12 @@ fn main() {
       .inspect(|gyro_data| {
         dbg!(gyro_data);
       })
+      .inspect_err(|err| println!("Reading gyro failed: {err}"))
       .ok();
 
     // let scan_result = i2c.scan();
-    // dbg!(scan_result);
+    // println!("{}", scan_result.table());
+    // println!("{scan_result}");
 
     debugln!("Waiting 5 seconds");
 
@@ -0,0 +1,120 @@
+use super::{cs_assert, ChipSelect, DeviceSettings, Error, Instance, Operation, Result, SPI};
+use crate::gpio;
+use core::cell::{RefCell, RefMut};
+use embedded_hal::spi as hal;
+
+/// Owns an SPI peripheral shared by several devices
+///
+/// The peripheral is deinitialized once the bus is dropped, which cannot
+/// happen while a [`Device`] still borrows it.
+///
+/// # Example
+///
+/// ```
+/// let bus = spi::Bus::new(Instance::SPI0);
+/// bus.init(1_000_000);
+///
+/// let mode_3 = Format {
+///   mode: MODE_3,
+///   ..Default::default()
+/// };
+///
+/// let imu = bus.device(Pin::new_init(17), DeviceSettings::new(Format::default(), 10_000_000));
+/// let sd = bus.device(Pin::new_init(22), DeviceSettings::new(mode_3, 25_000_000));
+/// ```
+pub struct Bus {
+  spi: RefCell<SPI>,
+}
+
+#[allow(unused)]
+impl Bus {
+  pub fn new(spi: Instance) -> Self {
+    Self {
+      spi: RefCell::new(SPI::new(spi)),
+    }
+  }
+
+  /// Initialize the SPI peripheral
+  ///
+  /// See [`SPI::init`].
+  pub fn init(&self, baudrate: u32) -> u32 {
+    self.spi.borrow_mut().init(baudrate)
+  }
+
+  /// Create a handle for the device behind `cs`
+  ///
+  /// The pin is configured as an output and driven high.
+  pub fn device(&self, cs: gpio::Pin, settings: DeviceSettings) -> Device<'_> {
+    Device::new(self, cs, settings)
+  }
+
+  /// Destroy the bus and return the peripheral
+  pub fn release(self) -> SPI {
+    self.spi.into_inner()
+  }
+}
+
+/// A device with its own chip select and settings on a shared [`Bus`]
+///
+/// The chip select is asserted for every transaction, and the bus is held
+/// exclusively until the transaction ends. The settings are applied when the
+/// previous transaction used different ones.
+///
+/// # Panics
+///
+/// Accessing the bus while another device is using it, e.g. from an interrupt
+/// handler, panics.
+pub struct Device<'a> {
+  bus: &'a Bus,
+  cs: gpio::Pin,
+  settings: DeviceSettings,
+}
+
+#[allow(unused)]
+impl<'a> Device<'a> {
+  pub fn new(bus: &'a Bus, cs: gpio::Pin, settings: DeviceSettings) -> Self {
+    cs.set_direction(gpio::Direction::OUT);
+    cs.put(true);
+
+    Self { bus, cs, settings }
+  }
+
+  /// Return the settings applied whenever this device is selected
+  pub fn settings(&self) -> DeviceSettings {
+    self.settings
+  }
+
+  /// Set the settings applied whenever this device is selected
+  pub fn set_settings(&mut self, settings: DeviceSettings) {
+    self.settings = settings;
+  }
+
+  /// Run a sequence of operations as a single transaction
+  ///
+  /// See [`SPI::transaction`].
+  pub fn transaction(&self, operations: &mut [Operation<'_, u8>]) -> Result<()> {
+    let spi = self.spi();
+    spi.reconfigure(self.settings);
+
+    cs_assert(&self.cs);
+    let _cs = ChipSelect(Some(self.cs));
+
+    operations
+      .iter_mut()
+      .try_for_each(|operation| spi.operation(operation))
+  }
+
+  fn spi(&self) -> RefMut<'a, SPI> {
+    self.bus.spi.borrow_mut()
+  }
+}
+
+impl hal::ErrorType for Device<'_> {
+  type Error = Error;
+}
+
+impl hal::SpiDevice for Device<'_> {
+  fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<()> {
+    Device::transaction(self, operations)
+  }
+}
@@ -5,3 +5,14 @@ pub const SPI1_BASE: u32 = 0x40040000;
 
 pub const SPI0_PTR: *mut spi_inst = SPI0_BASE as _;
 pub const SPI1_PTR: *mut spi_inst = SPI1_BASE as _;
+
+// Register offsets of the PL022 peripheral, used for the functions which are
+// `static inline` in the SDK and therefore have no symbol to link against.
+pub const SSPCR0: usize = 0x00;
+pub const SSPCR1: usize = 0x04;
+pub const SSPDR: usize = 0x08;
+
+pub const SSPCR0_DSS_BITS: u32 = 0x0000_000f;
+pub const SSPCR0_SPO_BITS: u32 = 0x0000_0040;
+pub const SSPCR0_SPH_BITS: u32 = 0x0000_0080;
+pub const SSPCR1_SSE_BITS: u32 = 0x0000_0002;
@@ -0,0 +1,216 @@
+use super::{defines, ChipSelect, Error, Result, SPI};
+use crate::dma::{self, Channel, Dreq};
+use core::marker::PhantomData;
+use core::ptr::addr_of_mut;
+use core::task::Poll;
+
+/// Byte `n` of this table holds the value `n`, so any fill value has a
+/// `'static` address the TX channel can read from
+static FILL: [u8; 256] = {
+  let mut table = [0u8; 256];
+  let mut i = 0;
+
+  while i < table.len() {
+    table[i] = i as u8;
+    i += 1;
+  }
+
+  table
+};
+
+/// Received bytes of write-only transfers end up here
+static mut SINK: u8 = 0;
+
+/// Reads of at least this many bytes within a transaction run on DMA
+const DMA_MIN_LEN: usize = 32;
+
+/// An SPI transfer running on a pair of DMA channels
+///
+/// Chip select stays asserted and the [`SPI`] stays borrowed until the
+/// transfer is dropped. Dropping an unfinished transfer aborts it, which is the
+/// only thing keeping the DMA from writing to a buffer that went out of scope:
+/// a transfer must never be leaked, e.g. with [`core::mem::forget`].
+///
+/// # Example
+///
+/// ```
+/// // The transfer is waited for before `buffer` goes out of scope
+/// let transfer = unsafe { spi.read_dma(0, &mut buffer)? };
+/// // ... do something useful ...
+/// let bytes = transfer.wait();
+/// ```
+#[must_use = "dropping the transfer aborts it"]
+pub struct DmaTransfer<'a> {
+  tx: Channel,
+  rx: Channel,
+  len: usize,
+  _cs: ChipSelect,
+  _borrow: PhantomData<&'a mut [u8]>,
+}
+
+impl DmaTransfer<'_> {
+  /// Has every byte been transferred?
+  pub fn is_complete(&self) -> bool {
+    // The RX channel finishes last, once the final byte was shifted in
+    !self.rx.is_busy()
+  }
+
+  /// Return the number of transferred bytes once the transfer is complete
+  pub fn poll(&self) -> Poll<usize> {
+    if self.is_complete() {
+      Poll::Ready(self.len)
+    } else {
+      Poll::Pending
+    }
+  }
+
+  /// Block until the transfer is complete and return the number of bytes
+  /// transferred
+  pub fn wait(self) -> usize {
+    while !self.is_complete() {}
+
+    self.len
+  }
+
+  /// Raise `DMA_IRQ_0` on completion, see [`dma::set_irq0_callback`]
+  pub fn enable_interrupt(&self) {
+    self.rx.set_irq0_enabled(true);
+  }
+}
+
+impl Drop for DmaTransfer<'_> {
+  fn drop(&mut self) {
+    if !self.is_complete() {
+      self.tx.abort();
+      self.rx.abort();
+    }
+  }
+}
+
+#[allow(unused)]
+impl SPI {
+  /// Start reading `dst.len()` bytes with DMA
+  ///
+  /// `repeated_tx_data` is output on TX for every byte, see [`SPI::read`].
+  ///
+  /// # Safety
+  ///
+  /// The returned transfer must be dropped or waited for, never leaked, see
+  /// [`DmaTransfer`].
+  pub unsafe fn read_dma<'a>(
+    &'a mut self,
+    repeated_tx_data: u8,
+    dst: &'a mut [u8],
+  ) -> Result<DmaTransfer<'a>> {
+    let cs = self.select();
+    let fill = &FILL[repeated_tx_data as usize];
+
+    unsafe { self.start_dma(cs, fill, false, dst.as_mut_ptr(), true, dst.len()) }
+  }
+
+  /// Start writing `src` with DMA, received bytes are discarded
+  ///
+  /// # Safety
+  ///
+  /// The returned transfer must be dropped or waited for, never leaked, see
+  /// [`DmaTransfer`].
+  pub unsafe fn write_dma<'a>(&'a mut self, src: &'a [u8]) -> Result<DmaTransfer<'a>> {
+    let cs = self.select();
+
+    unsafe { self.start_dma(cs, src.as_ptr(), true, addr_of_mut!(SINK), false, src.len()) }
+  }
+
+  /// Write `src` blocking, then start reading `dst.len()` bytes with DMA while
+  /// chip select stays asserted
+  ///
+  /// This is the DMA counterpart of [`SPI::transfer_slice`], meant for long
+  /// reads like FIFO drains.
+  ///
+  /// # Safety
+  ///
+  /// The returned transfer must be dropped or waited for, never leaked, see
+  /// [`DmaTransfer`].
+  pub unsafe fn transfer_dma<'a>(
+    &'a mut self,
+    src: &[u8],
+    dst: &'a mut [u8],
+  ) -> Result<DmaTransfer<'a>> {
+    let cs = self.select();
+    self.blocking_write(src)?;
+
+    unsafe { self.start_dma(cs, &FILL[0], false, dst.as_mut_ptr(), true, dst.len()) }
+  }
+
+  /// Read into `dst`, on DMA if it is long enough and a channel is free
+  ///
+  /// Chip select is left alone, this is the read of a running transaction.
+  pub(super) fn read_operation(&self, dst: &mut [u8]) -> Result<usize> {
+    if dst.len() >= DMA_MIN_LEN && !self.is_three_wire() {
+      // The transfer is waited for right away, so `dst` outlives it
+      let transfer = unsafe {
+        self.start_dma(
+          ChipSelect(None),
+          &FILL[0],
+          false,
+          dst.as_mut_ptr(),
+          true,
+          dst.len(),
+        )
+      };
+
+      match transfer {
+        Ok(transfer) => return Ok(transfer.wait()),
+        Err(Error::NoDmaChannel) => {}
+        Err(err) => return Err(err),
+      }
+    }
+
+    self.blocking_read(0, dst)
+  }
+
+  /// # Safety
+  ///
+  /// `tx` and `rx` must stay valid for the lifetime of the returned transfer
+  unsafe fn start_dma<'a>(
+    &'a self,
+    cs: ChipSelect,
+    tx: *const u8,
+    incr_tx: bool,
+    rx: *mut u8,
+    incr_rx: bool,
+    len: usize,
+  ) -> Result<DmaTransfer<'a>> {
+    // DMA always transfers full-duplex
+    if self.is_three_wire() {
+      return Err(Error::HalfDuplex);
+    }
+
+    // An unclaimed channel is released again when it goes out of scope
+    let (Some(tx_channel), Some(rx_channel)) = (Channel::claim(), Channel::claim()) else {
+      return Err(Error::NoDmaChannel);
+    };
+
+    let (tx_dreq, rx_dreq) = self.dreqs();
+    let dr = self.reg(defines::SSPDR).cast::<u8>();
+
+    tx_channel.configure(tx, incr_tx, dr, false, len, tx_dreq);
+    rx_channel.configure(dr, false, rx, incr_rx, len, rx_dreq);
+    dma::start(&[&tx_channel, &rx_channel]);
+
+    Ok(DmaTransfer {
+      tx: tx_channel,
+      rx: rx_channel,
+      len,
+      _cs: cs,
+      _borrow: PhantomData,
+    })
+  }
+
+  fn dreqs(&self) -> (Dreq, Dreq) {
+    if self.spi == defines::SPI1_PTR {
+      (Dreq::Spi1Tx, Dreq::Spi1Rx)
+    } else {
+      (Dreq::Spi0Tx, Dreq::Spi0Rx)
+    }
+  }
+}
@@ -0,0 +1,61 @@
+use super::defines::{SSPCR0_SPH_BITS, SSPCR0_SPO_BITS};
+pub use embedded_hal::spi::{Mode, Phase, Polarity, MODE_0, MODE_1, MODE_2, MODE_3};
+
+/// Frame format of an SPI transfer
+///
+/// Data words are always shifted out MSB first, the PL022 has no other order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Format {
+  /// Clock polarity and phase
+  pub mode: Mode,
+  /// Number of bits per transfer, 4..=16
+  ///
+  /// The read and write functions of [`SPI`](super::SPI) move bytes, so only
+  /// use other sizes together with code that handles the wider data words.
+  pub data_bits: u8,
+}
+
+impl Default for Format {
+  /// Mode 0, 8 data bits, MSB first
+  fn default() -> Self {
+    Self {
+      mode: MODE_0,
+      data_bits: 8,
+    }
+  }
+}
+
+impl Format {
+  /// Value of the `DSS`, `SPO` and `SPH` fields of `SSPCR0`
+  pub(super) fn cr0_bits(&self) -> u32 {
+    let mut bits = (self.data_bits as u32).saturating_sub(1);
+
+    if self.mode.polarity == Polarity::IdleHigh {
+      bits |= SSPCR0_SPO_BITS;
+    }
+
+    if self.mode.phase == Phase::CaptureOnSecondTransition {
+      bits |= SSPCR0_SPH_BITS;
+    }
+
+    bits
+  }
+}
+
+/// Settings of a single device on the bus, applied when it is selected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceSettings {
+  pub format: Format,
+  /// Highest clock the device supports, the bus picks the closest rate at or
+  /// below it
+  pub max_baudrate: u32,
+}
+
+impl DeviceSettings {
+  pub fn new(format: Format, max_baudrate: u32) -> Self {
+    Self {
+      format,
+      max_baudrate,
+    }
+  }
+}
@@ -1,18 +1,63 @@
+use crate::error::PicoErrorCodes;
+use crate::std::ffi::{c_int, ToResult};
+use crate::std::{thread, time::Duration};
 use crate::{
   gpio, spi_deinit, spi_get_baudrate, spi_init, spi_inst, spi_read_blocking, spi_set_baudrate,
-  spi_write_blocking, spi_write_read_blocking, PICO_DEFAULT_SPI_CSN_PIN,
+  spi_write_blocking, spi_write_read_blocking, GPIO_OVERRIDE_LOW, GPIO_OVERRIDE_NORMAL,
+  PICO_DEFAULT_SPI_CSN_PIN,
 };
+use core::cell::Cell;
+use core::fmt;
 use cortex_m::asm::nop;
+use embedded_hal::spi as hal;
+pub use embedded_hal::spi::Operation;
 
+pub use bus::{Bus, Device};
+pub use dma::DmaTransfer;
+pub use format::{DeviceSettings, Format, Mode, MODE_0, MODE_1, MODE_2, MODE_3};
+
+mod bus;
 mod defines;
+mod dma;
+mod format;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Error {
-  UnknownError,
+  /// Fewer bytes than requested were transferred
+  ShortTransfer {
+    requested: usize,
+    transferred: usize,
+  },
+  /// Every DMA channel is already claimed
+  NoDmaChannel,
+  /// Full-duplex transfer requested in 3-wire mode
+  HalfDuplex,
+  /// Any other status returned by the SDK
+  Pico(PicoErrorCodes),
 }
 
 pub type Result<T> = core::result::Result<T, Error>;
 
+impl hal::Error for Error {
+  fn kind(&self) -> hal::ErrorKind {
+    hal::ErrorKind::Other
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::ShortTransfer {
+        requested,
+        transferred,
+      } => write!(f, "short transfer, {transferred} of {requested} bytes"),
+      Error::NoDmaChannel => write!(f, "no free DMA channel"),
+      Error::HalfDuplex => write!(f, "full-duplex transfer in 3-wire mode"),
+      Error::Pico(code) => write!(f, "SDK error: {code}"),
+    }
+  }
+}
+
 #[allow(unused)]
 #[derive(Debug, Default)]
 pub enum Instance {
@@ -34,6 +79,11 @@ pub enum Instance {
 pub struct SPI {
   spi: *mut spi_inst,
   cs: Option<gpio::Pin>,
+  settings: Option<DeviceSettings>,
+  /// Settings the peripheral is configured with, `None` if unknown
+  applied: Cell<Option<DeviceSettings>>,
+  /// TX pin, released while reading in 3-wire mode
+  three_wire: Option<gpio::Pin>,
   initialized: bool,
 }
 
@@ -43,6 +93,9 @@ impl Default for SPI {
     Self {
       spi: defines::SPI0_PTR,
       cs: None,
+      settings: None,
+      applied: Cell::new(None),
+      three_wire: None,
       initialized: Default::default(),
     }
   }
@@ -59,10 +112,9 @@ impl SPI {
       SPI1 => defines::SPI1_PTR,
     };
 
-    Self {
-      spi,
-      ..Default::default()
-    }
+    let mut this = Self::default();
+    this.spi = spi;
+    this
   }
 
   pub fn with_chip_select(spi: Instance, cs_pin: gpio::Pin) -> Self {
@@ -73,11 +125,10 @@ impl SPI {
       SPI1 => defines::SPI1_PTR,
     };
 
-    Self {
-      spi,
-      cs: Some(cs_pin),
-      ..Default::default()
-    }
+    let mut this = Self::default();
+    this.spi = spi;
+    this.cs = Some(cs_pin);
+    this
   }
 
   /// Initialize SPI instance
@@ -91,11 +142,97 @@ impl SPI {
     }
 
     self.initialized = true;
-    unsafe { spi_init(self.spi, baudrate) }
+    self.applied.set(None);
+    let baudrate = unsafe { spi_init(self.spi, baudrate) };
+
+    match self.settings {
+      Some(settings) => self.apply(settings),
+      None => baudrate,
+    }
+  }
+
+  /// Set the frame format of the SPI
+  pub fn set_format(&self, format: Format) {
+    use defines::*;
+
+    debug_assert!((4..=16).contains(&format.data_bits));
+    self.applied.set(None);
+
+    // Same sequence as the inline `spi_set_format` of the SDK, the format may
+    // only change while the peripheral is disabled.
+    unsafe {
+      let cr0 = self.reg(SSPCR0);
+      let cr1 = self.reg(SSPCR1);
+      let enabled = cr1.read_volatile() & SSPCR1_SSE_BITS;
+
+      cr1.write_volatile(cr1.read_volatile() & !SSPCR1_SSE_BITS);
+      cr0.write_volatile(
+        (cr0.read_volatile() & !(SSPCR0_DSS_BITS | SSPCR0_SPO_BITS | SSPCR0_SPH_BITS))
+          | format.cr0_bits(),
+      );
+      cr1.write_volatile(cr1.read_volatile() | enabled);
+    }
+  }
+
+  /// Set the format and clock applied whenever this device is selected
+  ///
+  /// This lets devices with different modes or speed limits share a bus, each
+  /// [`SPI`] reconfigures the peripheral before asserting its chip select. The
+  /// peripheral is only reconfigured if it was set up differently since.
+  pub fn set_device_settings(&mut self, settings: Option<DeviceSettings>) {
+    self.settings = settings;
+  }
+
+  /// Return the settings applied whenever this device is selected
+  pub fn device_settings(&self) -> Option<DeviceSettings> {
+    self.settings
+  }
+
+  /// Share a single data line between writes and reads
+  ///
+  /// The RP2040 cannot receive on the TX pin, so the device's data pin is
+  /// wired to both TX and RX. While reading, the output of `tx` is disabled
+  /// and only the device drives the line. Full-duplex and DMA transfers fail
+  /// with [`Error::HalfDuplex`]. `None` returns to 4-wire mode.
+  pub fn set_three_wire(&mut self, tx: Option<gpio::Pin>) {
+    if let Some(ref tx) = self.three_wire {
+      tx.set_output_enable_override(GPIO_OVERRIDE_NORMAL);
+    }
+
+    self.three_wire = tx;
+  }
+
+  pub fn is_three_wire(&self) -> bool {
+    self.three_wire.is_some()
+  }
+
+  /// Pointer to a register of the peripheral
+  fn reg(&self, offset: usize) -> *mut u32 {
+    unsafe { self.spi.cast::<u8>().add(offset).cast() }
+  }
+
+  /// Apply device settings to the peripheral, returns the actual baudrate
+  fn apply(&self, settings: DeviceSettings) -> u32 {
+    self.set_format(settings.format);
+    let baudrate = self.set_baudrate(settings.max_baudrate);
+    self.applied.set(Some(settings));
+    baudrate
+  }
+
+  /// Apply device settings unless the peripheral already uses them
+  ///
+  /// Changing the format briefly disables the peripheral, and the baudrate
+  /// search is slow, so a device talking on its own does not pay for them on
+  /// every transaction.
+  fn reconfigure(&self, settings: DeviceSettings) {
+    if self.applied.get() != Some(settings) {
+      self.apply(settings);
+    }
   }
 
   /// Set SPI baudrate
   pub fn set_baudrate(&self, baudrate: u32) -> u32 {
+    self.applied.set(None);
     unsafe { spi_set_baudrate(self.spi, baudrate) }
   }
 
@@ -105,35 +242,44 @@ impl SPI {
   }
 
   fn cs_select(&self) {
-    if self.cs.and_then(|cs| Some(cs.get())).unwrap_or_default() {
-      for _ in 0..3 {
-        nop();
-      }
-
-      if let Some(ref cs) = self.cs {
-        cs.put(false);
-      }
+    if let Some(settings) = self.settings {
+      self.reconfigure(settings);
+    }
 
-      for _ in 0..3 {
-        nop();
-      }
+    if let Some(ref cs) = self.cs {
+      cs_assert(cs);
     }
   }
 
   fn cs_deselect(&self) {
-    if !self.cs.and_then(|cs| Some(cs.get())).unwrap_or_default() {
-      for _ in 0..3 {
-        nop();
-      }
+    if let Some(ref cs) = self.cs {
+      cs_release(cs);
+    }
+  }
 
-      if let Some(ref cs) = self.cs {
-        cs.put(true);
-      }
+  /// Assert chip select until the returned guard is dropped
+  fn select(&self) -> ChipSelect {
+    self.cs_select();
+    ChipSelect(self.cs)
+  }
 
-      for _ in 0..3 {
-        nop();
-      }
-    }
+  /// Run a sequence of operations as a single transaction
+  ///
+  /// Chip select stays asserted from the first operation to the last one, and
+  /// is released afterwards even if one of the operations fails.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// let mut data = [0u8; 6];
+  /// spi.transaction(&mut [Operation::Write(&[0x9F]), Operation::Read(&mut data)])?;
+  /// ```
+  pub fn transaction(&self, operations: &mut [Operation<'_, u8>]) -> Result<()> {
+    let _cs = self.select();
+
+    operations
+      .iter_mut()
+      .try_for_each(|operation| self.operation(operation))
   }
 
   /// Read from an SPI device
@@ -145,17 +291,9 @@ impl SPI {
   /// `0xff`
   pub fn read<const N: usize>(&self, repeated_tx_data: u8, stop: bool) -> Result<[u8; N]> {
     let mut dst: [u8; N] = [0x00; N];
-    self.cs_select();
-    let bytes = unsafe { spi_read_blocking(self.spi, repeated_tx_data, dst.as_mut_ptr(), N) };
-    if stop {
-      self.cs_deselect();
-    }
+    self.read_slice(repeated_tx_data, &mut dst, stop)?;
 
-    if bytes < 0 {
-      Err(Error::UnknownError)
-    } else {
-      Ok(dst)
-    }
+    Ok(dst)
   }
 
   /// Write to an SPI device, blocking.
@@ -164,17 +302,9 @@ impl SPI {
   /// until all data is transferred. No timeout, as SPI hardware always
   /// transfers at a known data rate.
   pub fn write<const N: usize>(&self, src: &[u8; N], stop: bool) -> Result<()> {
-    self.cs_select();
-    let bytes = unsafe { spi_write_blocking(self.spi, src.as_ptr(), N) };
-    if stop {
-      self.cs_deselect();
-    }
+    self.write_slice(src, stop)?;
 
-    if bytes < 0 {
-      Err(Error::UnknownError)
-    } else {
-      Ok(())
-    }
+    Ok(())
   }
 
   /// Write/Read to/from an SPI device.
@@ -184,14 +314,199 @@ impl SPI {
   /// transfers at a known data rate.
   pub fn write_read<const N: usize>(&self, src: &[u8; N]) -> Result<[u8; N]> {
     let mut dst: [u8; N] = [0x00; N];
+    self.write_read_slice(src, &mut dst)?;
+
+    Ok(dst)
+  }
+
+  /// Read `dst.len()` bytes from an SPI device
+  ///
+  /// Same as [`read`](Self::read), for buffers whose length is only known at
+  /// runtime. Returns the number of bytes read.
+  pub fn read_slice(&self, repeated_tx_data: u8, dst: &mut [u8], stop: bool) -> Result<usize> {
     self.cs_select();
-    let bytes = unsafe { spi_write_read_blocking(self.spi, src.as_ptr(), dst.as_mut_ptr(), N) };
-    self.cs_deselect();
+    let result = self.blocking_read(repeated_tx_data, dst);
+    if stop {
+      self.cs_deselect();
+    }
+
+    result
+  }
+
+  /// Write all of `src` to an SPI device
+  ///
+  /// Same as [`write`](Self::write), for buffers whose length is only known at
+  /// runtime. Returns the number of bytes written.
+  pub fn write_slice(&self, src: &[u8], stop: bool) -> Result<usize> {
+    self.cs_select();
+    let result = self.blocking_write(src);
+    if stop {
+      self.cs_deselect();
+    }
+
+    result
+  }
+
+  /// Write `src` while simultaneously reading into `dst`
+  ///
+  /// Same as [`write_read`](Self::write_read), for buffers whose length is
+  /// only known at runtime. Only the length of the shorter buffer is clocked,
+  /// and that length is returned.
+  pub fn write_read_slice(&self, src: &[u8], dst: &mut [u8]) -> Result<usize> {
+    let len = src.len().min(dst.len());
+
+    let _cs = self.select();
+    self.blocking_write_read(&src[..len], &mut dst[..len])
+  }
+
+  /// Write `src`, then read `dst.len()` bytes while chip select stays asserted
+  ///
+  /// This is the usual shape of a register access: a command or address
+  /// followed by the response. Returns the number of bytes read.
+  pub fn transfer_slice(&self, src: &[u8], dst: &mut [u8], stop: bool) -> Result<usize> {
+    self.cs_select();
+    let result = self
+      .blocking_write(src)
+      .and_then(|_| self.blocking_read(0, dst));
+    if stop {
+      self.cs_deselect();
+    }
+
+    result
+  }
+
+  /// Read into `dst`, releasing the shared data line in 3-wire mode
+  ///
+  /// The output of the TX pin is disabled for the duration of the read, and
+  /// handed back to the peripheral whether or not the read succeeded.
+  fn blocking_read(&self, repeated_tx_data: u8, dst: &mut [u8]) -> Result<usize> {
+    if let Some(ref tx) = self.three_wire {
+      tx.set_output_enable_override(GPIO_OVERRIDE_LOW);
+    }
+
+    let code =
+      unsafe { spi_read_blocking(self.spi, repeated_tx_data, dst.as_mut_ptr(), dst.len()) };
+
+    if let Some(ref tx) = self.three_wire {
+      tx.set_output_enable_override(GPIO_OVERRIDE_NORMAL);
+    }
+
+    to_result(code, dst.len())
+  }
+
+  fn blocking_write(&self, src: &[u8]) -> Result<usize> {
+    let code = unsafe { spi_write_blocking(self.spi, src.as_ptr(), src.len()) };
+
+    to_result(code, src.len())
+  }
+
+  fn blocking_write_read(&self, src: &[u8], dst: &mut [u8]) -> Result<usize> {
+    debug_assert_eq!(src.len(), dst.len());
+
+    if self.is_three_wire() && !src.is_empty() {
+      return Err(Error::HalfDuplex);
+    }
 
-    if bytes < 0 {
-      Err(Error::UnknownError)
-    } else {
-      Ok(dst)
+    let code =
+      unsafe { spi_write_read_blocking(self.spi, src.as_ptr(), dst.as_mut_ptr(), dst.len()) };
+
+    to_result(code, dst.len())
+  }
+
+  /// Run a single `embedded-hal` operation without touching chip select
+  fn operation(&self, operation: &mut Operation<'_, u8>) -> Result<()> {
+    match operation {
+      Operation::Read(buffer) => self.read_operation(buffer)?,
+      Operation::Write(bytes) => self.blocking_write(bytes)?,
+      Operation::Transfer(read, write) => {
+        // Clock out the common part full-duplex, then pad or discard whatever
+        // is left over on the longer side.
+        let common = read.len().min(write.len());
+        self.blocking_write_read(&write[..common], &mut read[..common])?;
+
+        if read.len() > common {
+          self.blocking_read(0, &mut read[common..])?;
+        } else if write.len() > common {
+          self.blocking_write(&write[common..])?;
+        }
+
+        common
+      }
+      Operation::TransferInPlace(buffer) => {
+        if self.is_three_wire() {
+          return Err(Error::HalfDuplex);
+        }
+
+        let code = unsafe {
+          spi_write_read_blocking(self.spi, buffer.as_ptr(), buffer.as_mut_ptr(), buffer.len())
+        };
+
+        to_result(code, buffer.len())?
+      }
+      Operation::DelayNs(ns) => {
+        thread::sleep(Duration::from_nanos(*ns as u64));
+        0
+      }
+    };
+
+    Ok(())
+  }
+}
+
+/// Map the return value of the SDK's blocking transfer functions
+fn to_result(code: c_int, requested: usize) -> Result<usize> {
+  match code.to_result() {
+    Ok(transferred) if transferred < requested => Err(Error::ShortTransfer {
+      requested,
+      transferred,
+    }),
+    Ok(transferred) => Ok(transferred),
+    Err(code) => Err(Error::Pico(code)),
+  }
+}
+
+impl hal::ErrorType for SPI {
+  type Error = Error;
+}
+
+impl hal::SpiDevice for SPI {
+  fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<()> {
+    SPI::transaction(self, operations)
+  }
+}
+
+fn cs_assert(cs: &gpio::Pin) {
+  for _ in 0..3 {
+    nop();
+  }
+
+  cs.put(false);
+
+  for _ in 0..3 {
+    nop();
+  }
+}
+
+fn cs_release(cs: &gpio::Pin) {
+  for _ in 0..3 {
+    nop();
+  }
+
+  cs.put(true);
+
+  for _ in 0..3 {
+    nop();
+  }
+}
+
+/// Releases chip select when dropped, so early returns cannot leave the device
+/// selected
+struct ChipSelect(Option<gpio::Pin>);
+
+impl Drop for ChipSelect {
+  fn drop(&mut self) {
+    if let Some(ref cs) = self.0 {
+      cs_release(cs);
     }
   }
 }
@@ -3,18 +3,24 @@
 pub use alloc::ffi::*;
 pub use core::ffi::*;
 
-pub type CResult<T = ()> = Result<T, c_int>;
+use crate::error::PicoErrorCodes;
+
+pub type CResult<T = ()> = Result<T, PicoErrorCodes>;
 
 pub trait ToResult {
-  fn to_result(&self) -> CResult;
+  /// Split an SDK return value into a count and an error
+  ///
+  /// Negative values are error codes, everything else is a count, e.g. of
+  /// the bytes transferred.
+  fn to_result(&self) -> CResult<usize>;
 }
 
 impl ToResult for c_int {
-  fn to_result(&self) -> CResult {
-    if self.is_positive() {
-      Err(*self)
+  fn to_result(&self) -> CResult<usize> {
+    if self.is_negative() {
+      Err(PicoErrorCodes::from(*self))
     } else {
-      Ok(())
+      Ok(*self as usize)
     }
   }
 }
@@ -17,6 +17,7 @@ impl ToAbsoluteTime for Duration {
   }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Instant(Duration);
 
 impl Instant {
@@ -4,5 +4,8 @@
 #include "pico/stdlib.h"
 #include "hardware/timer.h"
 #include "hardware/i2c.h"
+#include "pico/i2c_slave.h"
 #include "hardware/spi.h"
 #include "hardware/gpio.h"
+#include "hardware/dma.h"
+#include "hardware/irq.h"